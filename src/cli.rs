@@ -1,6 +1,7 @@
 use crate::info::cli;
 use clap::{Parser, ArgEnum, AppSettings::DeriveDisplayOrder};
 pub use clap::error as claperr;
+use std::time::Duration;
 
 pub static mut CLI_ARGS: Option<CliArgs> = None;
 
@@ -46,6 +47,26 @@ pub struct CliArgs {
 
 
 
+    #[clap(long, help = cli::ARG_MAX_STATES, value_name = "N")]
+    pub max_states: Option<usize>,
+
+    #[clap(long, help = cli::ARG_TIMEOUT, value_name = "SECONDS", parse(try_from_str = parse_seconds))]
+    pub timeout: Option<Duration>,
+
+    #[clap(long, help = cli::ARG_MAX_FRONTIER, value_name = "N")]
+    pub max_frontier: Option<usize>,
+
+    #[clap(long, help = cli::ARG_TOLERANCE, value_name = "DISTANCE", parse(try_from_str = parse_distance))]
+    pub tolerance: Option<f64>,
+
+    #[clap(long, help = cli::ARG_STRATEGY)]
+    pub strategy: Option<Strategy>,
+
+    #[clap(long, help = cli::ARG_COST)]
+    pub cost: Option<CostModel>,
+
+
+
     #[clap(long)]
     pub lint: Option<LintMode>,
 
@@ -54,6 +75,24 @@ pub struct CliArgs {
     pub explain: Option<String>,
 }
 
+fn parse_seconds(s: &str) -> Result<Duration, &'static str> {
+    s.parse::<f64>().ok()
+        .and_then(|t| Duration::try_from_secs_f64(t).ok())
+        .ok_or("expected a number of seconds")
+}
+
+fn parse_distance(s: &str) -> Result<f64, &'static str> {
+    s.parse::<f64>().ok()
+        .filter(|t| t.is_finite() && *t >= 0.0)
+        .ok_or("expected a non-negative number")
+}
+
+/// Whether output is written as markdown, which the printers ask for
+/// often enough not to copy all the arguments each time.
+pub fn markdown() -> bool {
+    unsafe { (*std::ptr::addr_of!(CLI_ARGS)).as_ref().is_some_and(|args| args.markdown) }
+}
+
 pub fn set_cli_args_empty() {
     unsafe {
         CLI_ARGS = Some(CliArgs{
@@ -92,6 +131,91 @@ impl std::fmt::Display for LintMode {
 }
 
 
-pub fn setup() {
-    unsafe { CLI_ARGS = Some(CliArgs::parse()); }
+#[derive(Copy, Clone, Debug, ArgEnum, PartialEq, Default)]
+pub enum Strategy {
+    #[default]
+    #[clap(name = cli::STRATEGY_SEARCH_NAME)] Search,
+    #[clap(name = cli::STRATEGY_EGRAPH_NAME)] EGraph,
+}
+
+impl std::str::FromStr for Strategy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            cli::STRATEGY_SEARCH_NAME => Ok(Strategy::Search),
+            cli::STRATEGY_EGRAPH_NAME => Ok(Strategy::EGraph),
+            _ => Err("invalid strategy"),
+        }
+    }
+}
+
+impl std::fmt::Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Strategy::Search => write!(f, "{}", cli::STRATEGY_SEARCH_NAME),
+            Strategy::EGraph => write!(f, "{}", cli::STRATEGY_EGRAPH_NAME),
+        }
+    }
+}
+
+
+pub fn setup() -> CliArgs {
+    let args = CliArgs::parse();
+    unsafe { CLI_ARGS = Some(args.clone()); }
+    args
+}
+
+
+#[derive(Copy, Clone, Debug, ArgEnum, PartialEq, Default)]
+pub enum CostModel {
+    #[default]
+    #[clap(name = cli::COST_COMBINED_NAME)] Combined,
+    #[clap(name = cli::COST_SIZE_NAME)] TermSize,
+    #[clap(name = cli::COST_DISTANCE_NAME)] SideDistance,
+    #[clap(name = cli::COST_SYMBOLS_NAME)] DistinctSymbols,
+}
+
+impl std::str::FromStr for CostModel {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            cli::COST_COMBINED_NAME => Ok(CostModel::Combined),
+            cli::COST_SIZE_NAME => Ok(CostModel::TermSize),
+            cli::COST_DISTANCE_NAME => Ok(CostModel::SideDistance),
+            cli::COST_SYMBOLS_NAME => Ok(CostModel::DistinctSymbols),
+            _ => Err("invalid cost"),
+        }
+    }
+}
+
+impl std::fmt::Display for CostModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CostModel::Combined => write!(f, "{}", cli::COST_COMBINED_NAME),
+            CostModel::TermSize => write!(f, "{}", cli::COST_SIZE_NAME),
+            CostModel::SideDistance => write!(f, "{}", cli::COST_DISTANCE_NAME),
+            CostModel::DistinctSymbols => write!(f, "{}", cli::COST_SYMBOLS_NAME),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeouts_are_non_negative_seconds() {
+        assert_eq!(parse_seconds("2.5"), Ok(Duration::from_millis(2500)));
+        assert!(parse_seconds("-1").is_err());
+        assert!(parse_seconds("soon").is_err());
+    }
+
+    #[test]
+    fn tolerances_are_non_negative_distances() {
+        assert_eq!(parse_distance("0.001"), Ok(0.001));
+        assert!(parse_distance("-0.1").is_err());
+        assert!(parse_distance("inf").is_err());
+    }
 }
@@ -22,10 +22,22 @@ pub mod cli {
 	static_string!(ARG_MARKDOWN, "Produce output in markdown form");
 	static_string!(ARG_COMPACT, "Produce compact output");
 	static_string!(ARG_QUIET, "Hide all output");
+	static_string!(ARG_MAX_STATES, "Give up on a question after considering N states");
+	static_string!(ARG_TIMEOUT, "Give up on a question after SECONDS seconds");
+	static_string!(ARG_MAX_FRONTIER, "Give up on a question when more than N states are waiting to be expanded");
+	static_string!(ARG_TOLERANCE, "Consider the sides of `~=` equal when they're at most DISTANCE apart");
+	static_string!(ARG_STRATEGY, "Prove questions by searching through rewrites (search) or by equality saturation (egraph)");
+	static_string!(ARG_COST, "Expand states with the fewest nodes (size), differences between sides (distance), distinct symbols (symbols), or nodes and differences (combined) first");
 	static_string!(ARG_EXPLAIN, "Explain the given error code");
 
 	pub const LINT_NONE_NAME: &str = "none";
 	pub const LINT_DIAG_NAME: &str = "diag";
+	pub const STRATEGY_SEARCH_NAME: &str = "search";
+	pub const STRATEGY_EGRAPH_NAME: &str = "egraph";
+	pub const COST_COMBINED_NAME: &str = "combined";
+	pub const COST_SIZE_NAME: &str = "size";
+	pub const COST_DISTANCE_NAME: &str = "distance";
+	pub const COST_SYMBOLS_NAME: &str = "symbols";
 }
 
 pub mod report {
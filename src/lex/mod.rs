@@ -117,8 +117,8 @@ impl Lexer {
 		// get ident
 		let ident = deref_source!(self).slice(self.start_offset, self.current_offset);
 
-		match <dyn Type>::from_string(ident.to_string()) {
-			Some(_) => self.make_token(Type),
+		match Type::from_string(ident) {
+			Some(_) => self.make_token(TokenKind::Type),
 			None => self.make_token(Identifier),
 		}
 	}
@@ -126,7 +126,7 @@ impl Lexer {
 	fn number(&mut self, first: char) -> Token {
 		let base = match (first, self.peek()) {
 			('0', 'b' | 'B') => { self.advance(); 2 },
-			('0', 'c' | 'C') => { self.advance(); 7 },
+			('0', 'c' | 'C') => { self.advance(); 8 },
 			('0', 'x' | 'X') => { self.advance(); 16 },
 			_ => 10,
 		};
@@ -143,7 +143,7 @@ impl Lexer {
 		let start = if base == 10 { self.start_offset } else { self.start_offset + 2 };
 		let base_str = match base {
 			2 => "binary",
-			7 => "octal",
+			8 => "octal",
 			10 => "decimal",
 			16 => "hexadecimal",
 			_ => unreachable!(),
@@ -169,6 +169,12 @@ impl Lexer {
 			}
 		}
 
+		// integers are arbitrarily large, but decimals aren't
+		let text = deref_source!(self).slice(start, self.current_offset);
+		if kind == Float && !text.parse::<f64>().is_ok_and(f64::is_finite) {
+			return formatted_error_token!(kind => self LiteralOverflow text);
+		}
+
 		self.make_token(kind)
 	}
 
@@ -255,6 +261,11 @@ impl Lexer {
 		}
 		if let Some(kind) = TokenKind::from_chars(&[c, self.peek()]) {
 			self.advance();
+
+			// numbered generic (e.g. `..0`)
+			if kind == Generic {
+				while self.peek().is_ascii_digit() { self.advance(); }
+			}
 			return self.make_token(kind);
 		}
 		if let Some(kind) = TokenKind::from_chars(&[c]) {
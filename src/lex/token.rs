@@ -1,4 +1,4 @@
-use super::span::Span;
+use super::span::{Span, Location};
 use crate::report::ecode::ErrorCode;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -18,6 +18,7 @@ pub enum TokenKind {
 	RightParen,
 	Define,
 	Colon,
+	Comma,
 	Access,
 	Dot,
 	
@@ -111,10 +112,10 @@ impl TokenKind {
 				'(' => __somekind!(LeftParen),
 				')' => __somekind!(RightParen),
 				':' => __somekind!(Colon),
+				',' => __somekind!(Comma),
 				'.' => __somekind!(Dot),
 	
 				'|' => __somekind!(Or),
-				'&' => __somekind!(And),
 				'~' => __somekind!(Not),
 				'>' => __somekind!(Greater),
 				'<' => __somekind!(Lesser),
@@ -139,4 +140,22 @@ impl TokenKind {
 pub struct Token {
 	pub kind: TokenKind,
 	pub span: Span
+}
+
+impl Token {
+	pub fn synthetic(kind: TokenKind) -> Self {
+		//! creates a token that doesn't originate from any source
+		Token {
+			kind,
+			span: Span {
+				start: Location {
+					file: String::new(),
+					line: None,
+					column: None,
+					source: std::ptr::null(),
+				},
+				length: 0,
+			}
+		}
+	}
 }
\ No newline at end of file
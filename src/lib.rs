@@ -10,4 +10,7 @@ pub mod runtime;
 pub mod cli;
 pub mod info;
 
+#[cfg(test)]
+mod testing;
+
 pub use cli::{CLI_ARGS, LintMode};
\ No newline at end of file
@@ -2,10 +2,11 @@ use mqs::*;
 
 use lex::Lexer;
 use parse::Parser; 
-use object::{obj_filename, {Disassembler, Assembler}};
+use object::{obj_filename, {Disassembler, Assembler}, question::AnswerType};
+use runtime::solver::Solver;
 use report::{ErrorCode, WarningCode, ReportableCode, lint};
 use info::report::{WCODE_PREFIX, ECODE_PREFIX};
-use cli::{CLI_ARGS, claperr};
+use cli::{CLI_ARGS, CliArgs, claperr};
 use std::{io::{Write, stderr}, path::PathBuf};
 use regex::Regex;
 
@@ -69,7 +70,7 @@ fn do_review(objfile: PathBuf, srcfile: String, error: report::Report) {
 
 		for q in &dis.questions {
 			q.stringify(&dis.strings).print();
-			true_count += q.is_true as i32;
+			true_count += (q.answer_type == AnswerType::True) as i32;
 			println!("");
 		}
 
@@ -81,7 +82,7 @@ fn do_review(objfile: PathBuf, srcfile: String, error: report::Report) {
 	}
 }
 
-fn do_file() {
+fn do_file(args: &CliArgs) {
 	let filename = get_cli_arg!(infile).unwrap();
 
 	let r = || -> Result<_, crate::report::Report> {
@@ -93,7 +94,8 @@ fn do_file() {
 
 		// assemble
 		let objf = obj_filename(filename.clone());
-		Assembler::new().asm(&context, objf);
+		let solver = Solver::new(&context).with_args(args);
+		Assembler::new().asm(&context, &solver, objf);
 
 		// temp
 		if !lint_mode_is!(Diag) {
@@ -112,7 +114,7 @@ fn do_file() {
 
 fn main() {
 	// parse cli args
-	cli::setup();
+	let args = cli::setup();
 	lint::prepare_lint();
 
 	if let Some(code) = get_cli_arg!(explain) {
@@ -164,7 +166,7 @@ fn main() {
 			let error = new_formatted_error!(CannotReview "uninterpreted file", filename);
 			do_review(objpath, filename, error);
 		}
-		else { do_file(); }
+		else { do_file(&args); }
 	}
 
 	lint::finish_lint();
@@ -1,5 +1,5 @@
-use super::question::{StringCollection, StringIndex};
-use crate::parse::{context::Context, astprinter::TheoryPrinter};
+use super::question::{StringCollection, StringIndex, IQuestion, IStep, SQuestion};
+use crate::{parse::context::Context, runtime::solver::Solver};
 
 use std::{io::Write, fs::File, path::PathBuf};
 
@@ -48,9 +48,41 @@ impl Assembler {
 		self.strings.len() - 1
 	}
 
-	pub fn asm(&mut self, context: &Context, path: PathBuf) {
+	fn add_question_strings(&mut self, q: &SQuestion) -> IQuestion {
+		let steps = q.steps.iter().map(|s| IStep {
+			description: self.add_string(&s.description),
+			process: self.add_string(&s.process),
+			state_before: self.add_string(&s.state_before),
+			state_after: self.add_string(&s.state_after),
+			conditions: s.conditions.iter().map(|c| self.add_string(c)).collect(),
+		}).collect();
+
+		IQuestion {
+			name: self.add_string(&q.name),
+			theory: self.add_string(&q.theory),
+			steps,
+			conclusion: self.add_string(&q.conclusion),
+			answer: self.add_string(&q.answer),
+			answer_type: q.answer_type,
+			counterexample: q.counterexample.iter().map(|c| self.add_string(c)).collect(),
+			solutions: q.solutions.iter().map(|s| self.add_string(s)).collect(),
+			solution_set: q.solution_set,
+			steps_tried: q.steps_tried,
+		}
+	}
+
+	pub fn asm(&mut self, context: &Context, solver: &Solver, path: PathBuf) {
+		// solve the questions and collect their strings first,
+		// so that the string index size is known in advance
+		let questions: Vec<IQuestion> = context.questions.iter()
+			.map(|q| solver.solve(q))
+			.collect::<Vec<SQuestion>>()
+			.iter()
+			.map(|q| self.add_question_strings(q))
+			.collect();
+
 		let stris = size_of_int(self.strings.len() as u64);
-		let qis = size_of_int(context.questions.len() as u64);
+		let qis = size_of_int(questions.len() as u64);
 
 		// header and checksum info will be inserted in the end
 		// to avoid checksum issues
@@ -59,41 +91,65 @@ impl Assembler {
 			//
 			($int:expr, $len:expr) => (self.write_bytes(&int_to_bytes($int as u64, $len)))
 		}
-		macro_rules! write_str {
-			($string:expr) => {{
-				let i = self.add_string(&$string);
-				write_int!(i, stris);
-			}}
-		}
 
 		// info (STRIS, QIS & question count)
 		self.write_byte(stris);
 		self.write_byte(qis);
-		write_int!(context.questions.len(), qis);
+		write_int!(questions.len(), qis);
 
 		// questions
-		for (i, q) in context.questions.iter().enumerate() {
+		for (i, q) in questions.iter().enumerate() {
 			// index, name & theory
 			write_int!(i, qis);
-			write_str!(q.name);
-			write_str!(TheoryPrinter::print(&q.theory));
+			write_int!(q.name, stris);
+			write_int!(q.theory, stris);
 
 			// SIS & step count
-			let sis = size_of_int(/* q.steps.len() */ 0);
+			let sis = size_of_int(q.steps.len() as u64);
 			self.write_byte(sis);
-			write_int!(/* q.steps.len() */ 0, sis);
+			write_int!(q.steps.len(), sis);
 
 			// steps
+			for (si, s) in q.steps.iter().enumerate() {
+				write_int!(si, sis);
+				write_int!(s.description, stris);
+				write_int!(s.process, stris);
+				write_int!(s.state_before, stris);
+				write_int!(s.state_after, stris);
+				let cis = size_of_int(s.conditions.len() as u64);
+				self.write_byte(cis);
+				write_int!(s.conditions.len(), cis);
+				for c in &s.conditions {
+					write_int!(*c, stris);
+				}
+			}
 
 			// conclusion, answer & answer type
-			write_str!(/* q.conclusion */ "some conclusion");
-			write_str!(/* q.answer */ "some answer");
-			self.write_byte(/* q.answer_type as u64 */ 1);
+			write_int!(q.conclusion, stris);
+			write_int!(q.answer, stris);
+			self.write_byte(q.answer_type as u8);
+
+			// counterexample
+			let uis = size_of_int(q.counterexample.len() as u64);
+			self.write_byte(uis);
+			write_int!(q.counterexample.len(), uis);
+			for c in &q.counterexample {
+				write_int!(*c, stris);
+			}
+
+			// solutions
+			let sois = size_of_int(q.solutions.len() as u64);
+			self.write_byte(sois);
+			write_int!(q.solutions.len(), sois);
+			self.write_byte(q.solution_set as u8);
+			for s in &q.solutions {
+				write_int!(*s, stris);
+			}
 
 			// STS & steps tried
-			let sts = size_of_int(/* q.steps_tried */ 123);
+			let sts = size_of_int(q.steps_tried);
 			self.write_byte(sts);
-			write_int!(/* q.steps_tried */ 123, sts);
+			write_int!(q.steps_tried, sts);
 		}
 		// strings
 		for (i, s) in self.strings.clone().iter().enumerate() {
@@ -1,4 +1,4 @@
-use super::question::{IQuestion, IStep, StringCollection, StringIndex};
+use super::question::{AnswerType, IQuestion, IStep, StringCollection, StringIndex};
 
 use std::{io::{BufReader, Read}, fs::File, path::PathBuf};
 
@@ -103,18 +103,47 @@ impl Disassembler {
 			for si in 0..scount {
 				test_or_error!(read_as!(sis, 8 => usize) == si => InvalidIndex "step");
 
+				let description = read_as!(stris, 8 => StringIndex);
+				let process = read_as!(stris, 8 => StringIndex);
+				let state_before = read_as!(stris, 8 => StringIndex);
+				let state_after = read_as!(stris, 8 => StringIndex);
+				let cis = read_as!(1, 1 => u8) as usize;
+				let ccount = read_as!(cis, 8 => usize);
+				let mut conditions = Vec::<StringIndex>::new();
+				for _ in 0..ccount {
+					conditions.push(read_as!(stris, 8 => StringIndex));
+				}
+
 				steps.push(IStep{
-					description: read_as!(stris, 8 => StringIndex),
-					process: read_as!(stris, 8 => StringIndex),
-					state_before: read_as!(stris, 8 => StringIndex),
-					state_after: read_as!(stris, 8 => StringIndex),
+					description,
+					process,
+					state_before,
+					state_after,
+					conditions,
 				});
 			}
 
 			// conclusion, etc..
 			let conclusion = read_as!(stris, 8 => StringIndex);
 			let answer = read_as!(stris, 8 => StringIndex);
-			let is_true = read_as!(1, 1 => u8) != 0;
+			let answer_type = AnswerType::try_from(read_as!(1, 1 => u8));
+			test_or_error!(answer_type.is_ok() => InvalidData);
+			let answer_type = answer_type.unwrap();
+
+			let uis = read_as!(1, 1 => u8) as usize;
+			let ccount = read_as!(uis, 8 => usize);
+			let mut counterexample = Vec::<StringIndex>::new();
+			for _ in 0..ccount {
+				counterexample.push(read_as!(stris, 8 => StringIndex));
+			}
+
+			let sois = read_as!(1, 1 => u8) as usize;
+			let scount = read_as!(sois, 8 => usize);
+			let solution_set = read_as!(1, 1 => u8) != 0;
+			let mut solutions = Vec::<StringIndex>::new();
+			for _ in 0..scount {
+				solutions.push(read_as!(stris, 8 => StringIndex));
+			}
 
 			let sts = read_as!(1, 1 => u8) as usize;
 			let steps_tried = read_as!(sts, 8 => u64);
@@ -126,7 +155,10 @@ impl Disassembler {
 				steps,
 				conclusion,
 				answer,
-				is_true,
+				answer_type,
+				counterexample,
+				solutions,
+				solution_set,
 				steps_tried,
 			});
 		}
@@ -1,15 +1,38 @@
 use crate::{new_formatted_error, get_cli_arg};
+use num_enum::TryFromPrimitive;
 
 pub type StringCollection = Vec<String>;
 pub type StringIndex = usize;
 
 static TAB: &str = "    ";
 
+/// What kind of answer a question got, stored as the answer type byte.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
+pub enum AnswerType {
+	False,
+	True,
+	/// neither proven nor disproven, such as when the solver gave up
+	Unknown,
+}
+
+impl std::fmt::Display for AnswerType {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			AnswerType::False => write!(f, "false"),
+			AnswerType::True => write!(f, "true"),
+			AnswerType::Unknown => write!(f, "unknown"),
+		}
+	}
+}
+
 pub struct Step<T> {
 	pub description: T,
 	pub process: T,
 	pub state_before: T,
 	pub state_after: T,
+	/// the side conditions the step relied on, along with why they hold
+	pub conditions: Vec<T>,
 }
 
 pub struct Question<T> {
@@ -18,7 +41,13 @@ pub struct Question<T> {
 	pub steps: Vec<Step<T>>,
 	pub conclusion: T,
 	pub answer: T,
-	pub is_true: bool,
+	pub answer_type: AnswerType,
+	/// values of the unknowns for which the theory doesn't hold, if found
+	pub counterexample: Vec<T>,
+	/// values of the unknowns for which an existence question holds
+	pub solutions: Vec<T>,
+	/// whether `solutions` holds all of them, rather than a single witness
+	pub solution_set: bool,
 	pub steps_tried: u64,
 }
 
@@ -34,6 +63,7 @@ impl IStep {
 			process: strings[self.process].clone(),
 			state_before: strings[self.state_before].clone(),
 			state_after: strings[self.state_after].clone(),
+			conditions: self.conditions.iter().map(|&c| strings[c].clone()).collect(),
 		}
 	}
 }
@@ -52,12 +82,27 @@ impl IQuestion {
 			steps,
 			conclusion: strings[self.conclusion].clone(),
 			answer: strings[self.answer].clone(),
-			is_true: self.is_true,
+			answer_type: self.answer_type,
+			counterexample: self.counterexample.iter().map(|&c| strings[c].clone()).collect(),
+			solutions: self.solutions.iter().map(|&s| strings[s].clone()).collect(),
+			solution_set: self.solution_set,
 			steps_tried: self.steps_tried,
 		}
 	}
 }
 
+impl SQuestion {
+	/// Labels the solutions of an existence question, if it has any.
+	fn solutions_line(&self) -> Option<(&str, String)> {
+		match (self.solution_set, self.solutions.is_empty()) {
+			(true, true) => Some(("solutions", String::from("none"))),
+			(true, false) => Some(("solutions", self.solutions.join("; "))),
+			(false, true) => None,
+			(false, false) => Some(("witness", self.solutions.join("; "))),
+		}
+	}
+}
+
 // normal printing
 impl SQuestion {
 	fn print_normal(&self) {
@@ -68,16 +113,31 @@ impl SQuestion {
 		for (i, s) in self.steps.iter().enumerate() {
 			println!("{}{}{}: {}", TAB, TAB, i + 1, s.description);
 			println!("{}{}{}`{}`", TAB, TAB, TAB, s.process);
+			for c in &s.conditions {
+				println!("{}{}{}given {}", TAB, TAB, TAB, c);
+			}
 		}
 		
 		println!("{}{}{}", TAB, TAB, self.conclusion);
-		println!("{}answer: {} ({})", TAB, self.answer, self.is_true);
+		if !self.counterexample.is_empty() {
+			println!("{}counterexample: {}", TAB, self.counterexample.join(", "));
+		}
+		if let Some((label, solutions)) = self.solutions_line() {
+			println!("{}{}: {}", TAB, label, solutions);
+		}
+		println!("{}answer: {} ({})", TAB, self.answer, self.answer_type);
 		println!("{}steps tried: {}", TAB, self.steps_tried);
 	}
 
 	fn print_at_normal(&self, step: usize) {
 		println!("question to solve: ?{} (step {})", self.name, step);
 		println!("{}theory: `{}`", TAB, self.theory);
+		if !self.counterexample.is_empty() {
+			println!("{}counterexample: {}", TAB, self.counterexample.join(", "));
+		}
+		if let Some((label, solutions)) = self.solutions_line() {
+			println!("{}{}: {}", TAB, label, solutions);
+		}
 
 		let step = &self.steps[step - 1];
 
@@ -86,6 +146,9 @@ impl SQuestion {
 
 		println!("{}step: {}", TAB, step.description);
 		println!("{}{}{}", TAB, TAB, step.process);
+		for c in &step.conditions {
+			println!("{}{}given {}", TAB, TAB, c);
+		}
 
 		println!("{}state after step:", TAB);
 		println!("{}{}`{}`", TAB, TAB, step.state_after);
@@ -103,16 +166,31 @@ impl SQuestion {
 		for (i, s) in self.steps.iter().enumerate() {
 			println!("&emsp;&emsp;{}: {} \\", i + 1, s.description);
 			println!("&emsp;&emsp;&emsp;${}$ \\", s.process);
+			for c in &s.conditions {
+				println!("&emsp;&emsp;&emsp;given {} \\", c);
+			}
 		}
 		
 		println!("&emsp;&emsp;{} \\", self.conclusion);
-		println!("&emsp;**answer:** {} ({}) \\", self.answer, self.is_true);
+		if !self.counterexample.is_empty() {
+			println!("&emsp;**counterexample:** ${}$ \\", self.counterexample.join(", "));
+		}
+		if let Some((label, solutions)) = self.solutions_line() {
+			println!("&emsp;**{}:** `{}` \\", label, solutions);
+		}
+		println!("&emsp;**answer:** {} ({}) \\", self.answer, self.answer_type);
 		println!("&emsp;**steps tried:** {}", self.steps_tried);
 	}
 
 	fn print_at_markdown(&self, step: usize) {
 		println!("**question to solve: *?{}*** (step {}) \\", self.name, step);
 		println!("&emsp;**theory:** ${}$ \\", self.theory);
+		if !self.counterexample.is_empty() {
+			println!("&emsp;**counterexample:** ${}$ \\", self.counterexample.join(", "));
+		}
+		if let Some((label, solutions)) = self.solutions_line() {
+			println!("&emsp;**{}:** `{}` \\", label, solutions);
+		}
 		println!("&emsp;**step {}:** \\", step);
 
 		let step = &self.steps[step - 1];
@@ -122,6 +200,9 @@ impl SQuestion {
 
 		println!("&emsp;&emsp;step: {} \\", step.description);
 		println!("&emsp;&emsp;&emsp;${}$ \\", step.process);
+		for c in &step.conditions {
+			println!("&emsp;&emsp;&emsp;given {} \\", c);
+		}
 
 		println!("&emsp;&emsp;state after step: \\");
 		println!("&emsp;&emsp;&emsp;${}$", step.state_after);
@@ -1,4 +1,43 @@
-use crate::lex::token::Token;
+use crate::{lex::token::Token, runtime::{types::Type, builtins::Builtin}};
+use num_bigint::BigInt;
+
+// ============= Declarations =============
+
+#[derive(Clone, Debug)]
+pub struct Parameter {
+	pub token: Token,
+	pub name: String,
+	pub annotation: Option<Type>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Function {
+	pub token: Token,
+	pub name: String,
+	pub parameters: Vec<Parameter>,
+	pub annotation: Option<Type>,
+	pub body: ExprNode,
+}
+
+#[derive(Clone, Debug)]
+pub struct Theorem {
+	pub token: Token,
+	pub name: String,
+	pub parameters: Vec<Parameter>,
+	pub lhs: ExprNode,
+	pub rhs: ExprNode,
+	/// the condition under which the theorem holds
+	pub guard: Option<TheoryNode>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Conclusion {
+	pub token: Token,
+	pub name: String,
+	pub parameters: Vec<Parameter>,
+	pub truth: bool,
+	pub theory: TheoryNode,
+}
 
 // ================ Theory ================
 
@@ -56,31 +95,69 @@ pub struct ExprNode {
 pub enum ExprItem {
 	Equality	{ lhs: Box<ExprNode>, rhs: Box<ExprNode> },
 	Term 		{ lhs: Box<ExprNode>, rhs: Box<ExprNode> },
-	Factor 		{ lhs: Box<ExprNode>, rhs: Box<ExprNode> },
+	/// `implicit` marks a product written by juxtaposition, as in `4 x`
+	Factor 		{ lhs: Box<ExprNode>, rhs: Box<ExprNode>, implicit: bool },
 	Unary		(Box<ExprNode>),
 	Power 		{ base: Box<ExprNode>, power: Box<ExprNode> },
 	Grouping	(Box<ExprNode>),
 	Variable	{path: String, expr: Box<ExprNode>},
+	Call		{path: String, args: Vec<ExprNode>, function: Box<Function>},
+	/// `parens` marks a call written as `sqrt(x)` rather than `sqrt x`
+	Builtin		{builtin: &'static Builtin, args: Vec<ExprNode>, parens: bool},
+	Symbol		(String),
+	Generic		(Option<usize>),
 	Literal		(Literal),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Literal {
-	Integer(u64),
+	Integer(BigInt),
+	/// decimal literals are approximations
 	Float(f64),
 }
 
+impl PartialEq for ExprNode {
+	fn eq(&self, other: &Self) -> bool {
+		//! structural equality, groupings are transparent
+		use ExprItem::*;
+
+		match (&self.item, &other.item) {
+			(Grouping(a), _) => a.as_ref() == other,
+			(_, Grouping(b)) => self == b.as_ref(),
+
+			(Equality { lhs: a, rhs: b }, Equality { lhs: c, rhs: d }) |
+			(Term { lhs: a, rhs: b }, Term { lhs: c, rhs: d }) |
+			(Factor { lhs: a, rhs: b, .. }, Factor { lhs: c, rhs: d, .. }) =>
+				self.token.kind == other.token.kind && a == c && b == d,
+
+			(Power { base: a, power: b }, Power { base: c, power: d }) => a == c && b == d,
+			(Unary(a), Unary(b)) => a == b,
+			(Variable { path: a, .. }, Variable { path: b, .. }) => a == b,
+			(Call { path: a, args: c, .. }, Call { path: b, args: d, .. }) => a == b && c == d,
+			(Builtin { builtin: a, args: c, .. }, Builtin { builtin: b, args: d, .. }) => a.name == b.name && c == d,
+			(Symbol(a), Symbol(b)) => a == b,
+			(Generic(a), Generic(b)) => a == b,
+			(Literal(a), Literal(b)) => a == b,
+			_ => false,
+		}
+	}
+}
+
 pub trait ExprVisitor<T> {
 	fn visit(&mut self, node: &ExprNode) -> T {
 		match &node.item {
 			ExprItem::Equality	{ lhs, rhs } 	=> self.visit_equality(node, lhs.as_ref(), rhs.as_ref()),
 			ExprItem::Term		{ lhs, rhs } 	=> self.visit_term(node, lhs.as_ref(), rhs.as_ref()),
-			ExprItem::Factor	{ lhs, rhs } 	=> self.visit_factor(node, lhs.as_ref(), rhs.as_ref()),
+			ExprItem::Factor	{ lhs, rhs, .. } => self.visit_factor(node, lhs.as_ref(), rhs.as_ref()),
 			ExprItem::Unary		( expr ) 	 	=> self.visit_unary(node, expr.as_ref()),
 			ExprItem::Power		{ base, power} 	=> self.visit_power(node, base.as_ref(), power.as_ref()),
 			ExprItem::Grouping	( expr ) 		=> self.visit_grouping(node, expr.as_ref()),
-			ExprItem::Variable	{ path, expr } 	=> self.visit_variable(node, &path, expr.as_ref()),
-			ExprItem::Literal	( literal ) 	=> self.visit_literal(node, &literal),
+			ExprItem::Variable	{ path, expr } 	=> self.visit_variable(node, path, expr.as_ref()),
+			ExprItem::Call		{ path, args, function } => self.visit_call(node, path, args, function.as_ref()),
+			ExprItem::Builtin	{ builtin, args, .. } => self.visit_builtin(node, builtin, args),
+			ExprItem::Symbol	( name ) 		=> self.visit_symbol(node, name),
+			ExprItem::Generic	( index ) 		=> self.visit_generic(node, *index),
+			ExprItem::Literal	( literal ) 	=> self.visit_literal(node, literal),
 		}
 	}
 	
@@ -90,6 +167,10 @@ pub trait ExprVisitor<T> {
 	fn visit_unary(&mut self, node: &ExprNode, expr: &ExprNode) -> T;
 	fn visit_power(&mut self, node: &ExprNode, base: &ExprNode, power: &ExprNode) -> T;
 	fn visit_grouping(&mut self, node: &ExprNode, expr: &ExprNode) -> T;
-	fn visit_variable(&mut self, node: &ExprNode, path: &String, expr: &ExprNode) -> T;
+	fn visit_variable(&mut self, node: &ExprNode, path: &str, expr: &ExprNode) -> T;
+	fn visit_call(&mut self, node: &ExprNode, path: &str, args: &[ExprNode], function: &Function) -> T;
+	fn visit_builtin(&mut self, node: &ExprNode, builtin: &Builtin, args: &[ExprNode]) -> T;
+	fn visit_symbol(&mut self, node: &ExprNode, name: &str) -> T;
+	fn visit_generic(&mut self, node: &ExprNode, index: Option<usize>) -> T;
 	fn visit_literal(&mut self, node: &ExprNode, literal: &Literal) -> T;
 }
\ No newline at end of file
@@ -1,9 +1,12 @@
-use super::ast::*;
-use crate::lex::token::TokenKind::{self, *};
+use super::ast::{self, *};
+use crate::{
+	lex::token::TokenKind::{self, *},
+	runtime::builtins::{Builtin, Notation},
+};
 
 macro_rules! normal_or_md {
 	($normal:expr, $md:expr) => {
-		if crate::get_cli_arg!(markdown) { $md }
+		if crate::cli::markdown() { $md }
 		else { $normal }
 	}
 }
@@ -93,6 +96,12 @@ impl TheoryPrinter {
 	pub fn print(root: &TheoryNode) -> String {
 		TheoryPrinter{}.visit(root)
 	}
+
+	pub fn print_decision(th: &TheoryNode, truth: &str) -> String {
+		let arrow = normal_or_md!("->", "\\longrightarrow");
+		let truth = normal_or_md!(truth.to_string(), format!("\\text{{{}}}", truth));
+		format!("{} {} {}", Self::print(th), arrow, truth)
+	}
 }
 
 // ================= Expr =================
@@ -121,6 +130,13 @@ impl ExprVisitor<String> for ExprPrinter {
 	}
 
 	fn visit_factor(&mut self, node: &ExprNode, lhs: &ExprNode, rhs: &ExprNode) -> String {
+		// juxtaposed numbers would read as a single one
+		if let ExprItem::Factor { implicit: true, .. } = node.item {
+			if !starts_with_number(rhs) {
+				return format!("{} {}", self.visit(lhs), self.visit(rhs));
+			}
+		}
+
 		let this = match node.token.kind {
 			Multiply => "*",
 			Divide => normal_or_md!("/", {
@@ -159,14 +175,64 @@ impl ExprVisitor<String> for ExprPrinter {
 		}
 	}
 
-	fn visit_variable(&mut self, _node: &ExprNode, path: &String, _expr: &ExprNode) -> String {
+	fn visit_variable(&mut self, _node: &ExprNode, path: &str, _expr: &ExprNode) -> String {
 		//
 		normal_or_md!(path.to_string(), format!("\\text{{{}}}", path))
 	}
+
+	fn visit_call(&mut self, _node: &ExprNode, path: &str, args: &[ExprNode], _function: &ast::Function) -> String {
+		let args = args.iter().map(|a| self.visit(a)).collect::<Vec<String>>().join(", ");
+		normal_or_md!(
+			format!("{}({})", path, args),
+			format!("\\text{{{}}}\\left( {} \\right)", path, args)
+		)
+	}
+
+	fn visit_builtin(&mut self, node: &ExprNode, builtin: &Builtin, args: &[ExprNode]) -> String {
+		let parens = matches!(node.item, ExprItem::Builtin { parens: true, .. });
+		let args = args.iter().map(|a| self.visit(a)).collect::<Vec<String>>();
+
+		normal_or_md!(
+			if parens {
+				format!("{}({})", builtin.name, args.join(", "))
+			} else {
+				format!("{} {}", builtin.name, args.join(" "))
+			},
+			match builtin.notation {
+				Notation::Enclosed(open, close) => format!("{}{}{}", open, args.join(", "), close),
+				Notation::Operator(op) if parens => format!("{}\\left( {} \\right)", op, args.join(", ")),
+				Notation::Operator(op) => format!("{} {}", op, args.join(" ")),
+			}
+		)
+	}
+
+	fn visit_symbol(&mut self, _node: &ExprNode, name: &str) -> String {
+		//
+		name.to_string()
+	}
+
+	fn visit_generic(&mut self, _node: &ExprNode, index: Option<usize>) -> String {
+		let index = index.map(|i| i.to_string()).unwrap_or_default();
+		normal_or_md!(format!("..{}", index), format!("\\space..{}\\space", index))
+	}
 }
 
 impl ExprPrinter {
 	pub fn print(root: &ExprNode) -> String {
 		ExprPrinter{}.visit(root)
 	}
+
+	pub fn print_rewrite(before: &ExprNode, after: &ExprNode) -> String {
+		let arrow = normal_or_md!("->", "\\longrightarrow");
+		format!("{} {} {}", Self::print(before), arrow, Self::print(after))
+	}
+}
+
+fn starts_with_number(expr: &ExprNode) -> bool {
+	match &expr.item {
+		ExprItem::Literal(_) => true,
+		ExprItem::Power { base, .. } => starts_with_number(base),
+		ExprItem::Factor { lhs, .. } => starts_with_number(lhs),
+		_ => false,
+	}
 }
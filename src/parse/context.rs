@@ -1,11 +1,15 @@
 use crate::runtime::question::Question;
 use std::collections::HashMap;
 
-use super::ast::ExprNode;
+use super::ast::{Conclusion, ExprNode, Function, Parameter, Theorem};
 
 #[derive(Clone, Debug)]
 pub struct Context {
 	pub variables: HashMap<String, ExprNode>,
+	pub unknowns: HashMap<String, Parameter>,
+	pub functions: HashMap<String, Function>,
+	pub theorems: HashMap<String, Theorem>,
+	pub conclusions: HashMap<String, Conclusion>,
 	pub questions: Vec<Question>,
 	pub sections: HashMap<String, Self>,
 }
@@ -14,6 +18,10 @@ impl Context {
 	pub fn new() -> Self {
 		Self {
 			variables: HashMap::new(),
+			unknowns: HashMap::new(),
+			functions: HashMap::new(),
+			theorems: HashMap::new(),
+			conclusions: HashMap::new(),
 			questions: Vec::new(),
 			sections: HashMap::new(),
 		}
@@ -28,10 +36,77 @@ impl Context {
 	}
 
 	pub fn set_variable(&mut self, name: String, expr: ExprNode) {
+		self.unknowns.remove(&name);
 		self.variables.insert(name, expr);
 	}
 
 	pub fn get_variable(&self, name: String) -> Option<&ExprNode> {
 		self.variables.get(&name)
 	}
-}
\ No newline at end of file
+
+	pub fn set_unknown(&mut self, name: String, unknown: Parameter) {
+		self.variables.remove(&name);
+		self.unknowns.insert(name, unknown);
+	}
+
+	pub fn get_unknown(&self, name: String) -> Option<&Parameter> {
+		self.unknowns.get(&name)
+	}
+
+	pub fn set_function(&mut self, name: String, function: Function) {
+		self.functions.insert(name, function);
+	}
+
+	pub fn get_function(&self, name: String) -> Option<&Function> {
+		self.functions.get(&name)
+	}
+
+	pub fn set_theorem(&mut self, name: String, theorem: Theorem) {
+		self.theorems.insert(name, theorem);
+	}
+
+	pub fn get_theorem(&self, name: String) -> Option<&Theorem> {
+		self.theorems.get(&name)
+	}
+
+	pub fn resolve_theorem(&self, path: &str) -> Option<&Theorem> {
+		//! resolves a path such as `std::!move_sub`
+		match path.split_once("::") {
+			Some((section, rest)) => self.sections.get(section)?.resolve_theorem(rest),
+			None => self.get_theorem(path.trim_start_matches('!').to_string()),
+		}
+	}
+
+	pub fn set_conclusion(&mut self, name: String, conclusion: Conclusion) {
+		self.conclusions.insert(name, conclusion);
+	}
+
+	pub fn get_conclusion(&self, name: String) -> Option<&Conclusion> {
+		self.conclusions.get(&name)
+	}
+
+	pub fn all_theorems(&self) -> Vec<(String, &Theorem)> {
+		//! all theorems in this context and its sections by their paths, sorted
+		self.all_by_path('!', |c| &c.theorems)
+	}
+
+	pub fn all_conclusions(&self) -> Vec<(String, &Conclusion)> {
+		//! all conclusions in this context and its sections by their paths, sorted
+		self.all_by_path('&', |c| &c.conclusions)
+	}
+
+	fn all_by_path<T>(&self, sigil: char, map: fn(&Self) -> &HashMap<String, T>) -> Vec<(String, &T)> {
+		let mut items: Vec<(String, &T)> = map(self).iter()
+			.map(|(name, item)| (format!("{}{}", sigil, name), item))
+			.collect();
+
+		for (section, context) in &self.sections {
+			for (path, item) in context.all_by_path(sigil, map) {
+				items.push((format!("{}::{}", section, path), item));
+			}
+		}
+
+		items.sort_by(|a, b| a.0.cmp(&b.0));
+		items
+	}
+}
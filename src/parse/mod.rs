@@ -8,21 +8,35 @@ use crate::{
 	SOURCES,
 	report::{error, Report},
 	lex::{Lexer, token::{*, TokenKind::*}},
-	runtime::question::Question as rQuestion,
+	runtime::{question::{Question as rQuestion, Limits, TOLERANCE}, types::Type, builtins::{self, Builtin}, eval::evaluate, soundness::counterexample},
 	new_formatted_error,
 	new_formatted_warning
 };
-use std::collections::HashMap;
+use std::{collections::{HashMap, HashSet}, time::Duration};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
 pub use context::Context;
 
-type PResult<T> = Result<T, Report>;
+/// Errors are boxed, since reports are large.
+type PResult<T> = Result<T, Box<Report>>;
 
 pub struct Parser {
 	context_stack: Vec<Context>,
 
 	apply_tokens: HashMap<String, Token>,
 	vardef_tokens: HashMap<String, Token>,
-
+	fndef_tokens: HashMap<String, Token>,
+	thmdef_tokens: HashMap<String, Token>,
+	concdef_tokens: HashMap<String, Token>,
+	/// unknowns that were used before being declared
+	implicit_unknowns: HashSet<String>,
+
+	parameters: Vec<Parameter>,
+	/// the limits of the questions in the file that don't set their own
+	defaults: Limits,
+	/// the tolerance of `~=` in the questions in the file that don't set their own
+	tolerance: Option<f64>,
+	allow_generics: bool,
 	had_error: bool,
 
 	next_token: usize,
@@ -100,9 +114,9 @@ impl Parser {
         if self.check(kind) {
             Ok(self.advance())
         } else {
-			Err(new_formatted_error!(ExpectedToken what.to_string())
+			Err(Box::new(new_formatted_error!(ExpectedToken what.to_string())
 				.with_quote(self.peek().span, Some("unexpected token here"))
-			)
+			))
         }
     }
 
@@ -122,6 +136,7 @@ impl Parser {
 				error(msg, Some(code))
 					.with_quote(self.peek().span, None::<String>)
 					.dispatch();
+				self.had_error = true;
 				
 				if let Some(fake) = fake {
 					self.tokens[self.next_token] = *fake;
@@ -194,10 +209,13 @@ impl Parser {
 			Apply => self.apply(),
 			Identifier => self.section(),
 			Variable => self.variable(),
+			Function => self.function(),
+			Theorem => self.theorem(),
+			Conclusion => self.conclusion(),
 			Question => self.question(),
-			_ => Err(new_formatted_error!(ExpectedTopLevel)
+			_ => Err(Box::new(new_formatted_error!(ExpectedTopLevel)
 					.with_quote(self.current().span.clone(), None::<String>)
-				)
+				))
 		}
 	}
 
@@ -227,10 +245,10 @@ impl Parser {
 		let fspath = match path.find_file() {
 			Ok(p) => p,
 			Err(why) => {
-				return Err(
+				return Err(Box::new(
 					new_formatted_error!(FailedToResolve path.to_string(), why)
 						.with_quote(token.span, None::<String>)
-				);
+				));
 			},
 		};
 
@@ -241,7 +259,7 @@ impl Parser {
 				if !path.has_prefix() {
 					report.message = report.message.replace(STDLIB_DIR, "");
 				}
-				return Err(report);
+				return Err(Box::new(report));
 			},
 		};
 
@@ -281,24 +299,181 @@ impl Parser {
 
 	fn variable(&mut self) -> PResult<()> {
 		let token = self.current();
-		let ident = self.consume(Identifier, "variable name")?
-			.span.get_part().unwrap_or("").to_string();
+		let name_token = self.consume(Identifier, "variable name")?;
+		let ident = name_token.span.get_part().unwrap_or("").to_string();
 		
-		self.consume(Define, ":=")?;
-		let expr = self.expression()?;
+		// `$x: type` declares an unknown instead of a variable
+		let unknown = if self.check(Colon) {
+			Some(self.annotation()?)
+		} else {
+			None
+		};
+		let expr = if unknown.is_none() {
+			self.consume(Define, ":=")?;
+			Some(self.expression()?)
+		} else {
+			None
+		};
 
-		if self.current_context().get_variable(ident.clone()).is_some() {
+		// declaring an unknown that was used before only gives it a type
+		let promotes = unknown.is_some()
+			&& self.current_context().get_unknown(ident.clone()).is_some()
+			&& self.implicit_unknowns.remove(&ident);
+		if !promotes && (self.current_context().get_variable(ident.clone()).is_some()
+		|| self.current_context().get_unknown(ident.clone()).is_some()) {
 			new_formatted_warning!(RedefenitionOf "variable" ident)
 				.with_quote(token.span.clone(), None::<String>)
 				.with_sub_quote(get_tok_span!(self vardef_tokens &ident), "previous definition here")
 				.dispatch();
 		}
-		self.current_context().set_variable(ident.clone(), expr);
+		match (expr, unknown) {
+			(Some(expr), _) => self.current_context().set_variable(ident.clone(), expr),
+			(None, annotation) => self.current_context().set_unknown(ident.clone(), Parameter {
+				token: name_token,
+				name: ident.clone(),
+				annotation: annotation.flatten(),
+			}),
+		}
 		self.vardef_tokens.insert(ident, token);
 
 		Ok(())
 	}
 
+	fn function(&mut self) -> PResult<()> {
+		let token = self.current();
+		let ident = self.consume(Identifier, "function name")?
+			.span.get_part().unwrap_or("").to_string();
+		let parameters = self.parameters()?;
+		let annotation = self.annotation()?;
+
+		// parse the body with the parameters in scope
+		self.consume(Define, ":=")?;
+		self.parameters = parameters.clone();
+		let body = self.expression();
+		self.parameters.clear();
+		let body = body?;
+
+		if self.current_context().get_function(ident.clone()).is_some() {
+			new_formatted_warning!(RedefenitionOf "function" ident)
+				.with_quote(token.span.clone(), None::<String>)
+				.with_sub_quote(get_tok_span!(self fndef_tokens &ident), "previous definition here")
+				.dispatch();
+		}
+		self.current_context().set_function(ident.clone(), ast::Function {
+			token: token.clone(),
+			name: ident.clone(),
+			parameters,
+			annotation,
+			body,
+		});
+		self.fndef_tokens.insert(ident, token);
+
+		Ok(())
+	}
+
+	fn theorem(&mut self) -> PResult<()> {
+		let token = self.current();
+		let ident = self.consume(Identifier, "theorem name")?
+			.span.get_part().unwrap_or("").to_string();
+		let parameters = if self.check(LeftParen) { self.parameters()? } else { Vec::new() };
+
+		// parse both sides with the parameters and generics in scope
+		self.parameters = parameters.clone();
+		self.allow_generics = true;
+		let sides = self.theorem_sides();
+		self.allow_generics = false;
+		self.parameters.clear();
+		let (lhs, rhs, guard) = sides?;
+
+		if self.current_context().get_theorem(ident.clone()).is_some() {
+			new_formatted_warning!(RedefenitionOf "theorem" ident)
+				.with_quote(token.span.clone(), None::<String>)
+				.with_sub_quote(get_tok_span!(self thmdef_tokens &ident), "previous definition here")
+				.dispatch();
+		}
+		let theorem = ast::Theorem {
+			token: token.clone(),
+			name: ident.clone(),
+			parameters,
+			lhs,
+			rhs,
+			guard,
+		};
+
+		// test the theorem numerically, so typos don't go unnoticed
+		let tolerance = self.tolerance.unwrap_or(TOLERANCE);
+		let context = self.current_context();
+		let annotation = |name: &str| match theorem.parameters.iter().find(|p| p.name == name) {
+			Some(p) => p.annotation,
+			None => context.get_unknown(name.to_string()).and_then(|p| p.annotation),
+		};
+		if let Some(counterexample) = counterexample(&theorem, tolerance, annotation) {
+			new_formatted_warning!(UnsoundTheorem ident)
+				.with_quote(token.span.clone(), None::<String>)
+				.with_note(format!("counterexample: {}", counterexample))
+				.dispatch();
+		}
+
+		self.current_context().set_theorem(ident.clone(), theorem);
+		self.thmdef_tokens.insert(ident, token);
+
+		Ok(())
+	}
+
+	fn theorem_sides(&mut self) -> PResult<(ExprNode, ExprNode, Option<TheoryNode>)> {
+		self.consume(Define, ":=")?;
+		let lhs = self.expression()?;
+		self.consume(Define, ":=")?;
+		let rhs = self.expression()?;
+
+		// an optional guard, as in `... := ..1 | ..0 != 0`
+		let guard = if self.matches(&[Or]) { Some(self.theory()?) } else { None };
+		Ok((lhs, rhs, guard))
+	}
+
+	fn conclusion(&mut self) -> PResult<()> {
+		let token = self.current();
+		let ident = self.consume(Identifier, "conclusion name")?
+			.span.get_part().unwrap_or("").to_string();
+		let parameters = if self.check(LeftParen) { self.parameters()? } else { Vec::new() };
+
+		// truth value, either `t` or `f`
+		let truth_token = self.consume(Identifier, "t` or `f")?;
+		let truth = match truth_token.span.get_part().unwrap_or("") {
+			"t" => true,
+			"f" => false,
+			_ => return Err(Box::new(new_formatted_error!(ExpectedToken "t` or `f")
+				.with_quote(truth_token.span, Some("unexpected token here"))
+			)),
+		};
+
+		// parse theory with the parameters and generics in scope
+		self.consume(Define, ":=")?;
+		self.parameters = parameters.clone();
+		self.allow_generics = true;
+		let th = self.theory();
+		self.allow_generics = false;
+		self.parameters.clear();
+		let theory = th?;
+
+		if self.current_context().get_conclusion(ident.clone()).is_some() {
+			new_formatted_warning!(RedefenitionOf "conclusion" ident)
+				.with_quote(token.span.clone(), None::<String>)
+				.with_sub_quote(get_tok_span!(self concdef_tokens &ident), "previous definition here")
+				.dispatch();
+		}
+		self.current_context().set_conclusion(ident.clone(), ast::Conclusion {
+			token: token.clone(),
+			name: ident.clone(),
+			parameters,
+			truth,
+			theory,
+		});
+		self.concdef_tokens.insert(ident, token);
+
+		Ok(())
+	}
+
 	fn question(&mut self) -> PResult<()> {
 		// get ident of next available number
 		let ident = if self.matches(&[Identifier]) {
@@ -307,22 +482,146 @@ impl Parser {
 			self.current_context().questions.len().to_string()
 		};
 		let token = self.current();
+		let parameters = if self.check(LeftParen) { self.parameters()? } else { Vec::new() };
+		let has_limits = self.check(LeftBrace);
+		let limits = if has_limits { self.limits()? } else { Limits::default() };
+
+		// how far apart the sides of `~=` may be, as in `~= 0.001`
+		let has_tolerance = self.check(RoughlyEquals);
+		let mut tolerance = None;
+		if has_tolerance {
+			let token = self.consume(RoughlyEquals, "~=")?;
+			tolerance = evaluate(&self.expression()?)
+				.map(|v| v.to_f64())
+				.filter(|t| t.is_finite() && *t >= 0.0);
+
+			// an invalid tolerance doesn't stop the rest of the question from being parsed
+			if tolerance.is_none() {
+				new_formatted_error!(InvalidTolerance)
+					.with_quote(token.span.clone(), None::<String>)
+					.dispatch();
+				self.had_error = true;
+			}
+		}
 
-		// TODO: parameters
+		// `?{ ... }` or `?~= ...` on its own sets those of the questions after it
+		if (has_limits || has_tolerance) && token.kind == Question && parameters.is_empty() && !self.check(Define) {
+			self.defaults = limits.or(&self.defaults);
+			self.tolerance = tolerance.or(self.tolerance);
+			return Ok(());
+		}
+		let limits = limits.or(&self.defaults);
+		let tolerance = tolerance.or(self.tolerance);
 
-		// parse theory
+		// parse theory with the parameters in scope
 		self.consume(Define, ":=")?;
-		let th = self.theory()?;
+		self.parameters = parameters.clone();
+		let th = self.theory();
+		self.parameters.clear();
+		let th = th?;
 
 		// add question to context and Ok
 		self.current_context().questions.push(rQuestion{
 			token,
 			name: ident,
+			parameters,
 			theory: th,
+			limits,
+			tolerance,
 		});
 
 		Ok(())
 	}
+
+	fn limits(&mut self) -> PResult<Limits> {
+		// search limits of a question, as in `{ max_states: 1000, timeout: 2.5 }`
+		self.consume(LeftBrace, '{')?;
+		let mut limits = Limits::default();
+
+		if !self.check(RightBrace) {
+			loop {
+				let token = self.consume(Identifier, "limit name")?;
+				let name = token.span.get_part().unwrap_or("").to_string();
+				self.consume(Colon, ':')?;
+				let value = evaluate(&self.expression()?);
+
+				let count = || value.as_ref()
+					.and_then(|v| v.to_integer()?.to_usize())
+					.filter(|n| *n > 0);
+				let seconds = || value.as_ref()
+					.and_then(|v| Duration::try_from_secs_f64(v.to_f64()).ok());
+
+				// invalid limits don't stop the rest of the question from being parsed
+				let report = match name.as_str() {
+					"max_states" => { limits.max_states = count(); limits.max_states.is_none().then_some("a positive integer") },
+					"max_frontier" => { limits.max_frontier = count(); limits.max_frontier.is_none().then_some("a positive integer") },
+					"timeout" => { limits.timeout = seconds(); limits.timeout.is_none().then_some("a number of seconds") },
+					_ => {
+						new_formatted_error!(UnknownLimit &name)
+							.with_quote(token.span.clone(), None::<String>)
+							.with_note("the search limits are `max_states`, `timeout` and `max_frontier`")
+							.dispatch();
+						self.had_error = true;
+						None
+					},
+				};
+				if let Some(expected) = report {
+					new_formatted_error!(InvalidLimit &name, expected)
+						.with_quote(token.span.clone(), None::<String>)
+						.dispatch();
+					self.had_error = true;
+				}
+
+				if !self.matches(&[Comma]) { break; }
+			}
+		}
+
+		self.consume(RightBrace, '}')?;
+		Ok(limits)
+	}
+
+}
+
+// parameter stuff
+impl Parser {
+	fn parameters(&mut self) -> PResult<Vec<Parameter>> {
+		self.consume(LeftParen, "(")?;
+		let mut parameters = Vec::<Parameter>::new();
+
+		if !self.check(RightParen) {
+			loop {
+				let token = self.consume(Identifier, "parameter name")?;
+				let name = token.span.get_part().unwrap_or("").to_string();
+
+				let annotation = self.annotation()?;
+
+				if let Some(prev) = parameters.iter().find(|p| p.name == name) {
+					new_formatted_error!(DuplicateParameter &name)
+						.with_quote(token.span.clone(), None::<String>)
+						.with_sub_quote(prev.token.span.clone(), "previous parameter here")
+						.dispatch();
+					self.had_error = true;
+				} else {
+					parameters.push(Parameter { token, name, annotation });
+				}
+
+				if !self.matches(&[Comma]) { break; }
+			}
+		}
+
+		self.consume(RightParen, ")")?;
+		Ok(parameters)
+	}
+
+	fn annotation(&mut self) -> PResult<Option<Type>> {
+		// optional type annotation
+		if self.matches(&[Colon]) {
+			let tok = self.consume(TokenKind::Type, "type")?;
+			Ok(Type::from_string(tok.span.get_part().unwrap_or("")))
+		} else {
+			Ok(None)
+		}
+	}
 }
 
 // theory stuff
@@ -346,8 +645,10 @@ impl Parser {
 	fn and(&mut self) -> PResult<TheoryNode> {
 		let mut th = self.not()?;
 
-		while self.matches(&[And]) {
-			let tok = self.current();
+		// `&` joins two theories, unless it starts the next conclusion
+		while self.check(Conclusion) && !self.at_conclusion() {
+			self.advance();
+			let tok = Token { kind: And, ..self.current() };
 			let rhs = self.not()?;
 			th = theory_node!(tok => Logical @s lhs: b!(th), rhs: b!(rhs) );
 		}
@@ -355,6 +656,17 @@ impl Parser {
 		Ok(th)
 	}
 
+	/// Whether the `&` that's next starts a conclusion declaration, as in
+	/// `&name(x: int) t :=`, which no theory joined by `&` looks like.
+	fn at_conclusion(&self) -> bool {
+		let header = self.tokens[self.next_token + 1..].iter()
+			.take_while(|t| t.kind != Define)
+			.collect::<Vec<&Token>>();
+		let fits = header.iter().all(|t| matches!(t.kind, Identifier | LeftParen | RightParen | Colon | Type | Comma));
+		let truth = header.last().is_some_and(|t| matches!(t.span.get_part(), Ok("t" | "f")));
+		fits && truth && self.tokens.get(self.next_token + 1 + header.len()).is_some_and(|t| t.kind == Define)
+	}
+
 	fn not(&mut self) -> PResult<TheoryNode> {
 		if self.matches(&[Not]) {
 			let tok = self.current();
@@ -384,62 +696,55 @@ impl Parser {
 		else if self.matches(&[Exists]) {
 			Ok(theory_node!(tok => Exists @t b!(atom) ))
 		}
+		// a parenthesized theory, as an operand of `&`, `|` or `~`
+		else if matches!(atom.item, TheoryItem::Grouping(_)) {
+			Ok(atom)
+		}
 
 		else { /* expected theory */
-			Err(new_formatted_error!(ExpectedTheory)
+			Err(Box::new(new_formatted_error!(ExpectedTheory)
 				.with_quote(self.peek().span, None::<String>)
-			)
+			))
 		}
 	}
 
 	fn atom(&mut self) -> PResult<TheoryNode> {
 		let token = self.peek();
 
-		if self.matches(&[LeftParen]) {
+		if self.check(LeftParen) {
 			// this is either a theory or an expression but we don't know yet
 			self.record_position();
+			self.advance();
 			let th = self.theory();
-			self.consume(TokenKind::RightParen, ")")?;
-			
-			if let Ok(th) = th {
+
+			if th.is_ok() && self.matches(&[RightParen]) {
 				// we got a proper theory, so return it.
 				self.pop_position();
-				Ok(theory_node!(token => Grouping @t b!(th)))
-			} else {
-				// we didn't get a theory, so it might be
-				// an expression, or an error
-				self.traceback();
-				let expr = self.expression();
-				self.consume(TokenKind::RightParen, ")")?;
-
-				match expr {
-					Ok(mut expr) => {
-						// wrap the expr in a grouping to keep its parens
-						expr = expr_node!(expr.token.clone() => Grouping @t b!(expr));
-						Ok(theory_node!(token => Expression @t expr))
-					},
-					Err(_) => Err(
-						new_formatted_error!(ExpectedTheoryOrExpression)
-							.with_quote(self.peek().span, None::<String>)
-					)
-				}
+				return Ok(theory_node!(token => Grouping @t b!(th?)));
 			}
-		} else {
-			// TODO: Change this?
 
-			let expr = self.expression()?;
-			match &expr.item {
-				// just leave it be
-				ExprItem::Unary{..} | 
-				ExprItem::Grouping{..} | 
-				ExprItem::Variable{..} | 
-				ExprItem::Literal{..} => Ok(theory_node!(token => Expression @t expr)),
-				
-				// wrap the expr in a grouping
-				_ => {
-					let group = expr_node!(expr.token.clone() => Grouping @t b!(expr));
-					Ok(theory_node!(group.token.clone() => Expression @t group))
-				}
+			// we didn't get a theory, so it's an expression that
+			// starts with a parenthesized one, as in `(x + 1)^2`
+			self.traceback();
+		}
+
+		let expr = self.expression()?;
+		match &expr.item {
+			// just leave it be
+			ExprItem::Unary{..} | 
+			ExprItem::Power{..} | 
+			ExprItem::Grouping{..} | 
+			ExprItem::Variable{..} | 
+			ExprItem::Call{..} | 
+			ExprItem::Builtin{..} | 
+			ExprItem::Symbol{..} | 
+			ExprItem::Generic{..} | 
+			ExprItem::Literal{..} => Ok(theory_node!(token => Expression @t expr)),
+			
+			// wrap the expr in a grouping
+			_ => {
+				let group = expr_node!(expr.token.clone() => Grouping @t b!(expr));
+				Ok(theory_node!(group.token.clone() => Expression @t group))
 			}
 		}
 	}
@@ -477,27 +782,35 @@ impl Parser {
 	}
 
 	fn factor(&mut self) -> PResult<ExprNode> {
-		let mut expr = self.power()?;
+		let mut expr = self.unary()?;
 
-		while self.matches(&[Multiply, Divide]) {
-			let tok = self.current();
-			let rhs = self.power()?;
-			expr = expr_node!(tok => Factor @s lhs: b!(expr), rhs: b!(rhs) );
+		loop {
+			if self.matches(&[Multiply, Divide]) {
+				let tok = self.current();
+				let rhs = self.unary()?;
+				expr = expr_node!(tok => Factor @s lhs: b!(expr), rhs: b!(rhs), implicit: false );
+			} else if self.juxtaposed() {
+				// `4 x`, the operand can't be negated as `4 -x` is a subtraction
+				let rhs = self.power()?;
+				let tok = Token { kind: Multiply, span: rhs.token.span.clone() };
+				expr = expr_node!(tok => Factor @s lhs: b!(expr), rhs: b!(rhs), implicit: true );
+			} else {
+				break;
+			}
 		}
 
 		Ok(expr)
 	}
 
-	fn power(&mut self) -> PResult<ExprNode> {
-		let mut expr = self.unary()?;
-
-		while self.matches(&[Power]) {
-			let tok = self.current();
-			let rhs = self.power()?;
-			expr = expr_node!(tok => Power @s base: b!(expr), power: b!(rhs) );
+	fn juxtaposed(&mut self) -> bool {
+		//! whether the next token starts the operand of an implicit multiplication
+		match self.peek().kind {
+			Integer | Float | Generic | LeftParen => true,
+			// an identifier followed by a brace opens a section instead
+			Identifier => self.tokens.get(self.next_token + 1)
+				.is_none_or(|t| t.kind != LeftBrace),
+			_ => false,
 		}
-
-		Ok(expr)
 	}
 
 	fn unary(&mut self) -> PResult<ExprNode> {
@@ -506,34 +819,56 @@ impl Parser {
 			let expr = self.unary()?;
 			Ok(expr_node!(tok => Unary @t b!(expr)))
 		} else {
-			self.primary()	
+			self.power()
 		}
 	}
 
+	fn power(&mut self) -> PResult<ExprNode> {
+		let mut expr = self.primary()?;
+
+		if self.matches(&[Power]) {
+			let tok = self.current();
+			let rhs = self.unary()?;
+			expr = expr_node!(tok => Power @s base: b!(expr), power: b!(rhs) );
+		}
+
+		Ok(expr)
+	}
+
 	fn primary(&mut self) -> PResult<ExprNode> {
 		let token = self.peek();
 
+		// the digits of literals are validated by the lexer
 		if self.matches(&[Integer]) {
-			let mut text = token.span.get_part().unwrap_or("0").to_string();
-			// let intval = text.parse::<u64>().unwrap();
-
-			let base = if text.len() >= 2 {
-				match text.chars().nth(1).unwrap() {
-					'b' | 'B' => { text = text[2..].to_string(); 2 },
-					'c' | 'C' => { text = text[2..].to_string(); 7 },
-					'x' | 'X' => { text = text[2..].to_string(); 16 },
-					_ => 10,
-				}
-			} else { 10 };
-			
-			let intval= u64::from_str_radix(&text, base);
-			Ok(expr_node!(token => Literal @t Literal::Integer(intval.unwrap_or(0))))
+			let text = token.span.get_part().unwrap_or("0");
+			let (digits, radix) = match text.get(..2) {
+				Some("0b" | "0B") => (&text[2..], 2),
+				Some("0c" | "0C") => (&text[2..], 8),
+				Some("0x" | "0X") => (&text[2..], 16),
+				_ => (text, 10),
+			};
+
+			let intval = BigInt::parse_bytes(digits.as_bytes(), radix).unwrap_or_default();
+			Ok(expr_node!(token => Literal @t Literal::Integer(intval)))
 		}
 		else if self.matches(&[Float]) {
 			let floatval = token.span.get_part().unwrap_or("0.0")
-				.parse::<f64>().unwrap();
+				.parse::<f64>().unwrap_or_default();
 			Ok(expr_node!(token => Literal @t Literal::Float(floatval)))
 		}
+		else if self.matches(&[Generic]) {
+			let text = token.span.get_part().unwrap_or("..");
+
+			if !self.allow_generics {
+				return Err(Box::new(new_formatted_error!(UnexpectedToken text)
+					.with_quote(token.span.clone(), None::<String>)
+					.with_note("generics can only be used in theorems and conclusions")
+				));
+			}
+
+			let index = text[2..].parse::<usize>().ok();
+			Ok(expr_node!(token => Generic @t index))
+		}
 		else if self.check(Identifier) {
 			self.variable_or_function()
 		}
@@ -544,9 +879,9 @@ impl Parser {
 		}
 		
 		else /* expected expression */ {
-			Err(new_formatted_error!(ExpectedExpression)
+			Err(Box::new(new_formatted_error!(ExpectedExpression)
 				.with_quote(self.peek().span, None::<String>)
-			)
+			))
 		}
 	}
 
@@ -569,34 +904,137 @@ impl Parser {
 			// a sub-section, so check if it exists.
 			match section.get_section(ident.clone()) {
 				Some(c) => section = c.to_owned(),
-				None => return Err(
+				None => return Err(Box::new(
 					new_formatted_error!(UseOfUndefined "section" ident, path.join("::"))
 						.with_quote(token.span, None::<String>)
 						.with_note(format!("try importing or defining the section `{}::{}`", path.join("::"), ident))
-				)
+				))
 			}
 			
 			path.push(ident.clone());
 		}
 
 		// finish var or func
-		if self.check(LeftParen) { unreachable!() }
+		let ident = path.last().unwrap().clone();
+		if self.check(LeftParen) && section.get_function(ident.clone()).is_some() {
+			self.finish_call(path, &section)
+		}
+		else if let Some(builtin) = self.builtin(&path, &section) {
+			self.finish_builtin(builtin)
+		}
 		else { self.finish_variable(path, &section) }
 	}
 
+	fn builtin(&self, path: &[String], section: &Context) -> Option<&'static Builtin> {
+		//! parameters, variables and unknowns shadow builtins
+		let ident = &path[0];
+		if path.len() != 1
+		|| self.parameters.iter().any(|p| &p.name == ident)
+		|| section.get_variable(ident.clone()).is_some()
+		|| section.get_unknown(ident.clone()).is_some() {
+			return None;
+		}
+		builtins::lookup(ident)
+	}
+
+	fn finish_builtin(&mut self, builtin: &'static Builtin) -> PResult<ExprNode> {
+		let token = self.current();
+
+		// either `gcd(a, b)` or `sqrt x`, whose arguments bind like a negation's
+		let parens = self.matches(&[LeftParen]);
+		let mut args = Vec::<ExprNode>::new();
+		if parens {
+			if !self.check(RightParen) {
+				loop {
+					args.push(self.expression()?);
+					if !self.matches(&[Comma]) { break; }
+				}
+			}
+			self.consume(RightParen, ")")?;
+		} else {
+			for _ in 0..builtin.arity {
+				args.push(self.unary()?);
+			}
+		}
+
+		if args.len() != builtin.arity {
+			return Err(Box::new(
+				new_formatted_error!(InvalidArgumentCount builtin.name, builtin.arity, args.len())
+					.with_quote(token.span, None::<String>)
+			));
+		}
+
+		Ok(expr_node!(token => Builtin @s builtin, args, parens))
+	}
+
+	fn finish_call(&mut self, path: Vec<String>, section: &Context) -> PResult<ExprNode> {
+		let token = self.current();
+		let ident = token.span.get_part().unwrap_or("");
+		let function = section.get_function(ident.to_string()).unwrap().clone();
+
+		// arguments
+		self.consume(LeftParen, "(")?;
+		let mut args = Vec::<ExprNode>::new();
+		if !self.check(RightParen) {
+			loop {
+				args.push(self.expression()?);
+				if !self.matches(&[Comma]) { break; }
+			}
+		}
+		self.consume(RightParen, ")")?;
+
+		if args.len() != function.parameters.len() {
+			return Err(Box::new(
+				new_formatted_error!(InvalidArgumentCount path.join("::"), function.parameters.len(), args.len())
+					.with_quote(token.span, None::<String>)
+					.with_sub_quote(function.token.span.clone(), "function defined here")
+			));
+		}
+
+		Ok(expr_node!(token => Call @s path: path.join("::"), args, function: b!(function)))
+	}
+
 	fn finish_variable(&mut self, path: Vec<String>, section: &Context) -> PResult<ExprNode> {
 		let token = self.current();
 		let ident = token.span.get_part().unwrap_or("");
+
+		// parameters shadow variables
+		if path.len() == 1 && self.parameters.iter().any(|p| p.name == ident) {
+			return Ok(expr_node!(token.clone() => Symbol @t ident.to_string()));
+		}
+
 		let expr = section.get_variable(ident.to_string());
 
 		if let Some(e) = expr {
 			Ok(expr_node!(token => Variable @s path: path.join("::"), expr: b!(e.clone())))
-		} else {
-			Err(
+		}
+		else if section.get_unknown(ident.to_string()).is_some() {
+			Ok(expr_node!(token => Symbol @t path.join("::")))
+		}
+		else if path.len() == 1 {
+			// implicitly declare an unknown, so it only gets reported once
+			new_formatted_warning!(ImplicitUnknown ident)
+				.with_quote(token.span.clone(), None::<String>)
+				.with_note(format!("consider declaring the unknown explicitly with `${}: real`", ident))
+				.dispatch();
+
+			let ident = ident.to_string();
+			self.current_context().set_unknown(ident.clone(), Parameter {
+				token: token.clone(),
+				name: ident.clone(),
+				annotation: None,
+			});
+			self.vardef_tokens.insert(ident.clone(), token.clone());
+			self.implicit_unknowns.insert(ident.clone());
+
+			Ok(expr_node!(token => Symbol @t ident))
+		}
+		else {
+			Err(Box::new(
 				new_formatted_error!(UseOfUndefined "variable" path.join("::"))
 					.with_quote(self.current().span, None::<String>)
 					.with_note(format!("consider defining the variable `{}`", path.join("::")))
-			)
+			))
 		}
 	}
 }
@@ -609,7 +1047,15 @@ impl Parser {
 
 			apply_tokens: HashMap::new(),
 			vardef_tokens: HashMap::new(),
-
+			fndef_tokens: HashMap::new(),
+			thmdef_tokens: HashMap::new(),
+			concdef_tokens: HashMap::new(),
+			implicit_unknowns: HashSet::new(),
+
+			parameters: Vec::new(),
+			defaults: Limits::default(),
+			tolerance: None,
+			allow_generics: false,
 			had_error: false,
 
 			next_token: 0,
@@ -618,7 +1064,7 @@ impl Parser {
 		}
 	}
 
-	pub fn parse(&mut self, filename: String, tokens: Vec<Token>) -> PResult<Context> {
+	pub fn parse(&mut self, filename: String, tokens: Vec<Token>) -> Result<Context, Report> {
 		self.tokens = tokens;
 		self.next_token = 0;
 
@@ -642,4 +1088,115 @@ impl Parser {
 			Ok(self.current_context().clone())
 		}
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ast::ExprItem, astprinter::ExprPrinter};
+	use std::time::Duration;
+	use crate::{runtime::types::Type, testing::{parse, fails_to_parse}};
+
+	#[test]
+	fn functions_are_stored_with_their_parameters() {
+		let script = parse("@f(x: int, y: real): int := 2 x");
+		let function = script.context.get_function("f".to_string()).unwrap();
+		assert_eq!(function.parameters.len(), 2);
+	}
+
+	#[test]
+	fn calls_need_as_many_arguments_as_parameters() {
+		assert!(!fails_to_parse("@f(x: int) := x\n?q := f(1) == 1"));
+		assert!(fails_to_parse("@f(x: int) := x\n?q := f(1, 2) == 1"));
+	}
+
+	#[test]
+	fn questions_keep_their_parameters() {
+		let script = parse("?q(x: int, y) := x == y");
+		let parameters = &script.question("q").parameters;
+		assert_eq!(parameters.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["x", "y"]);
+		assert_eq!(parameters[0].annotation, Some(Type::Int));
+		assert_eq!(parameters[1].annotation, None);
+	}
+
+	#[test]
+	fn parameters_have_distinct_names() {
+		assert!(fails_to_parse("?q(x: int, x: real) := x == x"));
+	}
+
+	#[test]
+	fn unknowns_are_kept_apart_from_variables() {
+		let script = parse("$x: real\n$e := x + 1");
+		assert!(script.context.get_variable("x".to_string()).is_none());
+		assert_eq!(script.context.get_unknown("x".to_string()).unwrap().annotation, Some(Type::Real));
+		assert!(matches!(script.variable("e").item, ExprItem::Term { .. }));
+	}
+
+	#[test]
+	fn undeclared_names_are_implicit_unknowns() {
+		let script = parse("$e := y + 1");
+		assert_eq!(script.context.get_unknown("y".to_string()).unwrap().annotation, None);
+	}
+
+	#[test]
+	fn declaring_an_implicit_unknown_gives_it_a_type() {
+		let script = parse("$e := y + 1\n$y: int");
+		assert_eq!(script.context.get_unknown("y".to_string()).unwrap().annotation, Some(Type::Int));
+	}
+
+	#[test]
+	fn juxtaposition_multiplies() {
+		let script = parse("$x: real\n$y: real\n$a := 2 x^2 y\n$b := (2 * (x^2)) * y");
+		assert_eq!(script.variable("a"), script.variable("b"));
+		assert!(matches!(script.variable("a").item, ExprItem::Factor { implicit: true, .. }));
+	}
+
+	#[test]
+	fn implicit_products_are_printed_as_written() {
+		let script = parse("$x: real\n$y: real\n$a := 4 x + 6 * y");
+		assert_eq!(ExprPrinter::print(script.variable("a")), "4 x + 6 * y");
+	}
+
+	#[test]
+	fn questions_keep_their_limits() {
+		let script = parse("?q {max_states: 10, timeout: 2.5} := 1 == 1");
+		let limits = &script.question("q").limits;
+		assert_eq!(limits.max_states, Some(10));
+		assert_eq!(limits.timeout, Some(Duration::from_millis(2500)));
+		assert_eq!(limits.max_frontier, None);
+	}
+
+	#[test]
+	fn limits_on_their_own_are_defaults() {
+		let script = parse("?{max_frontier: 5}\n?q := 1 == 1\n?r {max_frontier: 7} := 1 == 1");
+		assert_eq!(script.question("q").limits.max_frontier, Some(5));
+		assert_eq!(script.question("r").limits.max_frontier, Some(7));
+	}
+
+	#[test]
+	fn invalid_limits_are_reported() {
+		assert!(fails_to_parse("?q {max_states: 0} := 1 == 1"));
+		assert!(fails_to_parse("?q {timeout: -1} := 1 == 1"));
+		assert!(fails_to_parse("?q {max_steps: 10} := 1 == 1"));
+	}
+
+	#[test]
+	fn theorems_keep_their_guards() {
+		let script = parse("!t := ..0 / ..0 := 1 | ..0 != 0\n!u := ..0 + 0 := ..0");
+		assert!(script.theorem("t").guard.is_some());
+		assert!(script.theorem("u").guard.is_none());
+	}
+
+	#[test]
+	fn questions_keep_their_tolerance() {
+		let script = parse("?q ~= 0.01 := 1 ~= 1\n?~= 0.5\n?r := 1 ~= 1\n?s(x) {max_states: 5} ~= 2 := x ~= x");
+		assert_eq!(script.question("q").tolerance, Some(0.01));
+		assert_eq!(script.question("r").tolerance, Some(0.5));
+		assert_eq!(script.question("s").tolerance, Some(2.0));
+	}
+
+	#[test]
+	fn tolerances_are_not_negative() {
+		assert!(fails_to_parse("?q ~= -1 := 1 ~= 1"));
+		assert!(fails_to_parse("?~= 1 / 0"));
+	}
+}
@@ -20,6 +20,7 @@ pub enum ErrorCode {
 	_L = 100, // lexical-error codes
 	UnexpectedChar,
 	InvalidDigit,
+	LiteralOverflow,
 
 	_S = 200, // syntax-error codes
 	ExpectedToken,
@@ -28,10 +29,13 @@ pub enum ErrorCode {
 	ExpectedDeclaration,
 	ExpectedTheory,
 	ExpectedExpression,
-	ExpectedTheoryOrExpression,
 	AlreadyDefined,
 	UseOfUndefined,
 	DuplicateParameter,
+	InvalidArgumentCount,
+	UnknownLimit,
+	InvalidLimit,
+	InvalidTolerance,
 
 	_D = 300, // disassembly-error codes
 	MissingData,
@@ -93,6 +97,7 @@ macro_rules! fmt_error_msg {
 	
 	(UnexpectedChar $chr:expr) => (format!("unexpected character {:?}", $chr));
 	(InvalidDigit $chr:expr, $base:expr, $t:expr) => (format!("invalid digit {:?} in {} {}", $chr, $base, $t));
+	(LiteralOverflow $lit:expr) => (format!("float literal `{}` is too large to be represented", $lit));
 
 	(ExpectedToken $tok:expr) => (format!("expected token `{}`", $tok));
 	(UnexpectedToken $tok:expr) => (format!("unexpected token `{}`", $tok));
@@ -100,11 +105,14 @@ macro_rules! fmt_error_msg {
 	(ExpectedDeclaration) => ("expected a declaration");
 	(ExpectedTheory) => ("expected a theory");
 	(ExpectedExpression) => ("expected an expression");
-	(ExpectedTheoryOrExpression) => ("expected a theory or expression");
 	(AlreadyDefined $type:tt $name:expr) => (format!("{} `{}` already defined", $type, $name));
 	(UseOfUndefined $type:tt $name:expr) => (format!("use of undefined {} `{}`", $type, $name));
 	(UseOfUndefined $type:tt $name:expr, $section:expr) => (format!("use of undefined {} `{}` in section `{}`", $type, $name, $section));
 	(DuplicateParameter $param:expr) => (format!("duplicate parameter `{}`", $param));
+	(InvalidArgumentCount $func:expr, $expected:expr, $got:expr) => (format!("function `{}` takes {} argument(s) but {} were given", $func, $expected, $got));
+	(UnknownLimit $name:expr) => (format!("unknown search limit `{}`", $name));
+	(InvalidLimit $name:expr, $expected:expr) => (format!("search limit `{}` must be {}", $name, $expected));
+	(InvalidTolerance) => ("tolerance must be a non-negative number");
 
 	(MissingData) => ("missing data");
 	(InvalidData) => ("invalid data");
@@ -8,6 +8,8 @@ pub enum WarningCode {
 	NoWarning = 0,
 	RedefenitionOf,
 	ShadowingApplication,
+	ImplicitUnknown,
+	UnsoundTheorem,
 }
 
 impl super::ReportableCode for WarningCode {
@@ -31,4 +33,6 @@ macro_rules! fmt_warning_msg {
 	(NoWarning) => ("there is no warning, why did this appear?");
 	(RedefenitionOf $what:tt $name:expr) => (format!("redefenition of {} '{}'", $what, $name));
 	(ShadowingApplication $name:expr) => (format!("application of section '{}' shadows previous application", $name));
+	(ImplicitUnknown $name:expr) => (format!("implicit declaration of unknown '{}'", $name));
+	(UnsoundTheorem $name:expr) => (format!("theorem '{}' doesn't hold everywhere", $name));
 }
\ No newline at end of file
@@ -0,0 +1,134 @@
+use super::eval::Number;
+use num_rational::BigRational;
+use num_traits::Signed;
+use num_integer::Integer;
+
+/// How a builtin is printed in markdown (LaTeX).
+#[derive(Clone, Copy, Debug)]
+pub enum Notation {
+	/// an operator such as `\sin`, followed by its argument(s)
+	Operator(&'static str),
+	/// delimiters around the argument(s), like `\sqrt{x}` or `|x|`
+	Enclosed(&'static str, &'static str),
+}
+
+/// A mathematical function that's always available.
+#[derive(Debug)]
+pub struct Builtin {
+	pub name: &'static str,
+	pub arity: usize,
+	pub notation: Notation,
+	pub eval: fn(&[Number]) -> Option<Number>,
+}
+
+macro_rules! builtin {
+	($name:literal $arity:literal $notation:expr => $eval:expr) => {
+		Builtin { name: $name, arity: $arity, notation: $notation, eval: $eval }
+	};
+}
+
+use Notation::*;
+
+const BUILTINS: &[Builtin] = &[
+	builtin!("sqrt"  1 Enclosed("\\sqrt{", "}") => |a| sqrt(&a[0])),
+	builtin!("abs"   1 Enclosed("\\left| ", " \\right|") => |a| abs(&a[0])),
+	builtin!("floor" 1 Enclosed("\\left\\lfloor ", " \\right\\rfloor") => |a| round(&a[0], BigRational::floor, f64::floor)),
+	builtin!("ceil"  1 Enclosed("\\left\\lceil ", " \\right\\rceil") => |a| round(&a[0], BigRational::ceil, f64::ceil)),
+	builtin!("sin"   1 Operator("\\sin") => |a| real(&a[0], f64::sin)),
+	builtin!("cos"   1 Operator("\\cos") => |a| real(&a[0], f64::cos)),
+	builtin!("tan"   1 Operator("\\tan") => |a| real(&a[0], f64::tan)),
+	builtin!("ln"    1 Operator("\\ln") => |a| positive(&a[0], f64::ln)),
+	builtin!("log"   1 Operator("\\log") => |a| positive(&a[0], f64::log10)),
+	builtin!("exp"   1 Operator("\\exp") => |a| real(&a[0], f64::exp)),
+	builtin!("min"   2 Operator("\\min") => |a| Some(if a[1] < a[0] { &a[1] } else { &a[0] }.clone())),
+	builtin!("max"   2 Operator("\\max") => |a| Some(if a[1] > a[0] { &a[1] } else { &a[0] }.clone())),
+	builtin!("gcd"   2 Operator("\\gcd") => |a| Some(Number::integer(a[0].to_integer()?.gcd(&a[1].to_integer()?)))),
+	builtin!("lcm"   2 Operator("\\operatorname{lcm}") => |a| Some(Number::integer(a[0].to_integer()?.lcm(&a[1].to_integer()?)))),
+];
+
+/// Returns the builtin function with the given name, if there is one.
+pub fn lookup(name: &str) -> Option<&'static Builtin> {
+	BUILTINS.iter().find(|b| b.name == name)
+}
+
+// ============== evaluators ==============
+
+fn real(n: &Number, f: fn(f64) -> f64) -> Option<Number> {
+	Number::approx(f(n.to_f64()))
+}
+
+fn positive(n: &Number, f: fn(f64) -> f64) -> Option<Number> {
+	if n.is_negative() || n.is_zero() { None } else { real(n, f) }
+}
+
+fn sqrt(n: &Number) -> Option<Number> {
+	//! stays exact for squares of rationals
+	if n.is_negative() { return None; }
+
+	if let Number::Exact(v) = n {
+		let (numer, denom) = (v.numer().sqrt(), v.denom().sqrt());
+		let root = BigRational::new(numer, denom);
+		if &(&root * &root) == v {
+			return Some(Number::Exact(root));
+		}
+	}
+	real(n, f64::sqrt)
+}
+
+fn abs(n: &Number) -> Option<Number> {
+	match n {
+		Number::Exact(v) => Some(Number::Exact(v.abs())),
+		Number::Approx(v) => Some(Number::Approx(v.abs())),
+	}
+}
+
+fn round(n: &Number, exact: fn(&BigRational) -> BigRational, approx: fn(f64) -> f64) -> Option<Number> {
+	match n {
+		Number::Exact(v) => Some(Number::Exact(exact(v))),
+		Number::Approx(v) => Number::from_f64_integer(approx(*v)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn call(name: &str, args: &[Number]) -> Option<Number> {
+		let builtin = lookup(name).unwrap();
+		assert_eq!(builtin.arity, args.len());
+		(builtin.eval)(args)
+	}
+
+	fn ratio(numer: i64, denom: i64) -> Number {
+		Number::Exact(BigRational::new(numer.into(), denom.into()))
+	}
+
+	#[test]
+	fn square_roots_of_squares_stay_exact() {
+		assert_eq!(call("sqrt", &[ratio(9, 4)]), Some(ratio(3, 2)));
+		assert!(call("sqrt", &[Number::integer(2)]).unwrap().is_approx());
+		assert!(call("sqrt", &[Number::integer(-1)]).is_none());
+	}
+
+	#[test]
+	fn integer_functions() {
+		assert_eq!(call("gcd", &[Number::integer(12), Number::integer(18)]), Some(Number::integer(6)));
+		assert_eq!(call("lcm", &[Number::integer(4), Number::integer(6)]), Some(Number::integer(12)));
+		assert!(call("gcd", &[ratio(1, 2), Number::integer(2)]).is_none());
+		assert_eq!(call("floor", &[ratio(-3, 2)]), Some(Number::integer(-2)));
+		assert_eq!(call("abs", &[ratio(-3, 2)]), Some(ratio(3, 2)));
+	}
+
+	#[test]
+	fn functions_outside_their_domain_are_undefined() {
+		assert!(call("ln", &[Number::integer(0)]).is_none());
+		assert!(call("log", &[Number::integer(-10)]).is_none());
+		assert_eq!(call("log", &[Number::integer(100)]).map(|n| n.to_f64()), Some(2.0));
+	}
+
+	#[test]
+	fn unknown_names_are_not_builtins() {
+		assert!(lookup("sqrt").is_some());
+		assert!(lookup("frobnicate").is_none());
+	}
+}
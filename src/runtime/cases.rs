@@ -0,0 +1,203 @@
+use super::{
+	eval::Number,
+	divisibility::Divisibility,
+	existence::rational_sqrt,
+	polynomial::Polynomial,
+	rewrite::outermost_expressions,
+	soundness::{free_unknowns, holds_at},
+	types::Type,
+};
+use crate::{
+	parse::ast::{ExprNode, TheoryNode, TheoryItem},
+	lex::token::TokenKind::*,
+};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The largest amount of cases a theory is split into.
+const MAX_CASES: usize = 256;
+
+/// A case of a case split, along with whether the theory holds in it.
+pub struct Case {
+	pub description: String,
+	pub holds: bool,
+}
+
+/// The theories a combination of theories is made of.
+fn atoms(th: &TheoryNode) -> Vec<&TheoryNode> {
+	match &th.item {
+		TheoryItem::Grouping(t) | TheoryItem::Unary(t) => atoms(t),
+		TheoryItem::Logical { lhs, rhs } => [atoms(lhs), atoms(rhs)].concat(),
+		_ => vec![th],
+	}
+}
+
+fn atom_expr(th: &TheoryNode) -> Option<&ExprNode> {
+	match &th.item {
+		TheoryItem::Expression(e) => Some(e),
+		TheoryItem::Grouping(t) => atom_expr(t),
+		_ => None,
+	}
+}
+
+/// The difference of the sides of an exact comparison of two polynomials.
+fn difference(th: &TheoryNode) -> Option<Polynomial> {
+	let (TheoryItem::Comparison { lhs, rhs } | TheoryItem::Implies { lhs, rhs }) = &th.item else { return None };
+	// how far apart approximately equal sides may be doesn't depend on their signs
+	if th.token.kind == RoughlyEquals { return None; }
+	Some(Polynomial::from_expr(atom_expr(lhs)?)?.sub(&Polynomial::from_expr(atom_expr(rhs)?)?))
+}
+
+/// Splits a combination of theories into cases its atoms don't change
+/// their truth value in, and decides it in each of them. Divisibilities
+/// by constants in integer unknowns are split on the residues of their
+/// unknowns, comparisons of polynomials in a single unknown on the signs
+/// of their sides' differences. `tolerance` is how far apart the sides of
+/// `~=` may be, and `annotation` gives the type of an unknown, if it has
+/// one. Returns `None` if the theory can't be split, or if it can't be
+/// decided in one of the cases.
+pub fn split(th: &TheoryNode, tolerance: f64, annotation: impl Fn(&str) -> Option<Type>) -> Option<Vec<Case>> {
+	let atoms = atoms(th);
+	let names: BTreeSet<String> = outermost_expressions(th).into_iter()
+		.flat_map(|(_, e)| free_unknowns(e))
+		.collect();
+	if names.is_empty() { return None; }
+
+	let samples = residues(&atoms, &names, &annotation).or_else(|| signs(&atoms, &names, &annotation))?;
+	samples.into_iter()
+		.map(|(description, values)| Some(Case { description, holds: holds_at(th, &values, tolerance)? }))
+		.collect()
+}
+
+/// One case per residue of every unknown modulo the least common
+/// multiple of the divisors.
+fn residues(atoms: &[&TheoryNode], names: &BTreeSet<String>, annotation: &impl Fn(&str) -> Option<Type>) -> Option<Vec<(String, BTreeMap<String, Number>)>> {
+	let mut modulus = BigInt::one();
+	for atom in atoms {
+		let TheoryItem::Divisible { expr, divisor } = &atom.item else { return None };
+		let div = Divisibility::new(atom_expr(expr)?, atom_expr(divisor)?, |name| annotation(name) == Some(Type::Int))?;
+		modulus = modulus.lcm(&div.divisor);
+	}
+	if names.iter().any(|name| annotation(name) != Some(Type::Int)) { return None; }
+
+	let m = modulus.to_usize()?;
+	let count = m.checked_pow(names.len().try_into().ok()?)?;
+	if count > MAX_CASES { return None; }
+
+	let cases = (0..count).map(|mut i| {
+		let mut values = BTreeMap::new();
+		let mut residues = Vec::new();
+		for name in names {
+			residues.push(format!("{} = {}", name, i % m));
+			values.insert(name.clone(), Number::integer(i % m));
+			i /= m;
+		}
+		(format!("{} (mod {})", residues.join(", "), m), values)
+	});
+	Some(cases.collect())
+}
+
+/// One case per root of the differences of the sides of every comparison,
+/// and one per interval between them, for a single unknown.
+fn signs(atoms: &[&TheoryNode], names: &BTreeSet<String>, annotation: &impl Fn(&str) -> Option<Type>) -> Option<Vec<(String, BTreeMap<String, Number>)>> {
+	let [name] = names.iter().collect::<Vec<&String>>()[..] else { return None };
+	let is_integer = annotation(name) == Some(Type::Int);
+
+	let mut roots = Vec::new();
+	for atom in atoms {
+		let p = difference(atom)?;
+		if p.as_constant().is_some() { continue; }
+
+		let (_, c) = p.univariate()?;
+		match c.as_slice() {
+			[c0, c1] => roots.push(-c0 / c1),
+			[c0, c1, c2] => {
+				let discriminant = c1 * c1 - BigRational::from_integer(4.into()) * c2 * c0;
+				if discriminant.is_negative() { continue; }
+
+				let s = rational_sqrt(&discriminant)?;
+				let two_a = c2 * BigRational::from_integer(2.into());
+				roots.push((-c1 - &s) / &two_a);
+				roots.push((-c1 + s) / two_a);
+			},
+			_ => return None,
+		}
+	}
+	roots.sort();
+	roots.dedup();
+
+	let sample = |value: BigRational| BTreeMap::from([(name.clone(), Number::Exact(value))]);
+	let one = BigRational::one();
+	let mut cases = Vec::new();
+	for i in 0..=roots.len() {
+		let (lo, hi) = (i.checked_sub(1).map(|i| &roots[i]), roots.get(i));
+		let value = match (lo, hi, is_integer) {
+			(None, None, _) => Some(BigRational::zero()),
+			(None, Some(b), true) => Some(b.ceil() - &one),
+			(None, Some(b), false) => Some(b - &one),
+			(Some(a), None, true) => Some(a.floor() + &one),
+			(Some(a), None, false) => Some(a + &one),
+			(Some(a), Some(b), true) => Some(a.floor() + &one).filter(|v| v < b),
+			(Some(a), Some(b), false) => Some((a + b) / BigRational::from_integer(2.into())),
+		};
+		let description = match (lo, hi) {
+			(None, None) => format!("any {}", name),
+			(None, Some(b)) => format!("{} < {}", name, b),
+			(Some(a), None) => format!("{} > {}", name, a),
+			(Some(a), Some(b)) => format!("{} < {} < {}", a, name, b),
+		};
+		if let Some(value) = value {
+			cases.push((description, sample(value)));
+		}
+
+		if let Some(root) = hi.filter(|r| !is_integer || r.is_integer()) {
+			cases.push((format!("{} = {}", name, root), sample(root.clone())));
+		}
+	}
+	Some(cases)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{runtime::question::TOLERANCE, testing::parse};
+
+	/// The cases `?q` is split into, with `n` an integer.
+	fn cases(src: &str) -> Option<Vec<(String, bool)>> {
+		let script = parse(src);
+		let annotation = |name: &str| (name == "n").then_some(Type::Int);
+		split(script.theory("q"), TOLERANCE, annotation)
+			.map(|cases| cases.into_iter().map(|c| (c.description, c.holds)).collect())
+	}
+
+	#[test]
+	fn comparisons_are_split_on_signs() {
+		let found = cases("?q := x >= 0 | x < 0").unwrap();
+		assert_eq!(found.len(), 3);
+		assert!(found.iter().all(|(_, holds)| *holds));
+		assert!(found.iter().any(|(d, _)| d == "x = 0"));
+	}
+
+	#[test]
+	fn divisibilities_are_split_on_residues() {
+		let found = cases("?q := n % 2 | (n + 1) % 2").unwrap();
+		assert_eq!(found, vec![
+			(String::from("n = 0 (mod 2)"), true),
+			(String::from("n = 1 (mod 2)"), true),
+		]);
+	}
+
+	#[test]
+	fn failing_cases_are_kept() {
+		let found = cases("?q := x^2 - 1 >= 0 | x > 0").unwrap();
+		assert!(found.iter().any(|(_, holds)| !holds));
+	}
+
+	#[test]
+	fn theories_without_unknowns_arent_split() {
+		assert!(cases("?q := 1 > 0 | 2 < 0").is_none());
+	}
+}
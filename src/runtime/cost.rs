@@ -0,0 +1,138 @@
+use super::rewrite::{children, subexpressions, theory_children};
+use crate::{cli::CostModel, parse::{ast::*, astprinter::ExprPrinter}};
+use std::collections::{HashMap, HashSet};
+
+/// Estimates how far a state is from being decided,
+/// the solver expands states with lower costs first.
+pub trait Cost {
+	fn cost(&self, state: &TheoryNode) -> usize;
+}
+
+/// The number of nodes in all expressions of the state.
+pub struct TermSize;
+
+impl Cost for TermSize {
+	fn cost(&self, state: &TheoryNode) -> usize {
+		subexpressions(state).into_iter()
+			.filter(|(_, e)| !matches!(e.item, ExprItem::Grouping(_)))
+			.count()
+	}
+}
+
+/// The number of sub-expressions that only occur on one side of
+/// a match or comparison, summed over all of them in the state.
+pub struct SideDistance;
+
+impl SideDistance {
+	fn occurrences(expr: &ExprNode, out: &mut HashMap<String, isize>, sign: isize) {
+		if !matches!(expr.item, ExprItem::Grouping(_)) {
+			*out.entry(ExprPrinter::print(expr)).or_default() += sign;
+		}
+		for child in children(expr) {
+			Self::occurrences(child, out, sign);
+		}
+	}
+
+	fn atom(th: &TheoryNode) -> Option<&ExprNode> {
+		match &th.item {
+			TheoryItem::Expression(e) => Some(e),
+			TheoryItem::Grouping(t) => Self::atom(t),
+			_ => None,
+		}
+	}
+}
+
+impl Cost for SideDistance {
+	fn cost(&self, state: &TheoryNode) -> usize {
+		let sides = match &state.item {
+			TheoryItem::Implies { lhs, rhs } |
+			TheoryItem::Comparison { lhs, rhs } => Self::atom(lhs).zip(Self::atom(rhs)),
+			_ => None,
+		};
+
+		match sides {
+			Some((lhs, rhs)) => {
+				let mut occurrences = HashMap::new();
+				Self::occurrences(lhs, &mut occurrences, 1);
+				Self::occurrences(rhs, &mut occurrences, -1);
+				occurrences.values().map(|n| n.unsigned_abs()).sum()
+			},
+			None => theory_children(state).into_iter().map(|t| self.cost(t)).sum(),
+		}
+	}
+}
+
+/// The number of distinct unknowns, variables and functions in the state.
+pub struct DistinctSymbols;
+
+impl Cost for DistinctSymbols {
+	fn cost(&self, state: &TheoryNode) -> usize {
+		subexpressions(state).into_iter()
+			.filter_map(|(_, e)| match &e.item {
+				ExprItem::Symbol(name) |
+				ExprItem::Variable { path: name, .. } |
+				ExprItem::Call { path: name, .. } => Some(name),
+				_ => None,
+			})
+			.collect::<HashSet<&String>>()
+			.len()
+	}
+}
+
+/// The sum of several costs.
+pub struct Combined(pub Vec<Box<dyn Cost>>);
+
+impl Cost for Combined {
+	fn cost(&self, state: &TheoryNode) -> usize {
+		self.0.iter().map(|c| c.cost(state)).sum()
+	}
+}
+
+impl Default for Combined {
+	fn default() -> Self {
+		Combined(vec![Box::new(TermSize), Box::new(SideDistance)])
+	}
+}
+
+/// The cost chosen with `--cost`.
+pub fn from_model(model: CostModel) -> Box<dyn Cost> {
+	match model {
+		CostModel::Combined => Box::new(Combined::default()),
+		CostModel::TermSize => Box::new(TermSize),
+		CostModel::SideDistance => Box::new(SideDistance),
+		CostModel::DistinctSymbols => Box::new(DistinctSymbols),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::parse;
+
+	fn cost(cost: &dyn Cost, theory: &str) -> usize {
+		cost.cost(parse(&format!("$x: real\n$y: real\n?q := {}", theory)).theory("q"))
+	}
+
+	#[test]
+	fn term_size_counts_nodes() {
+		assert_eq!(cost(&TermSize, "x + 1 == 2"), 4);
+		assert_eq!(cost(&TermSize, "(x + 1) == 2"), 4);
+	}
+
+	#[test]
+	fn side_distance_counts_unshared_subexpressions() {
+		assert_eq!(cost(&SideDistance, "x + 1 <=> x + 1"), 0);
+		assert_eq!(cost(&SideDistance, "x + 1 <=> x + 2"), 4);
+	}
+
+	#[test]
+	fn distinct_symbols_are_counted_once() {
+		assert_eq!(cost(&DistinctSymbols, "x + y + x == 1"), 2);
+	}
+
+	#[test]
+	fn combined_cost_is_the_sum() {
+		let theory = "x + 1 <=> x + 2";
+		assert_eq!(cost(&Combined::default(), theory), cost(&TermSize, theory) + cost(&SideDistance, theory));
+	}
+}
@@ -0,0 +1,142 @@
+use super::{polynomial::Polynomial, eval::evaluate};
+use crate::parse::ast::ExprNode;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::collections::BTreeMap;
+
+/// The largest amount of cases (residues of all unknowns together)
+/// that are checked one by one before giving up.
+const MAX_CASES: u64 = 4096;
+
+/// A divisibility `p % d` of a polynomial with integer coefficients
+/// in integer unknowns by a non-zero integer constant.
+pub struct Divisibility {
+	pub poly: Polynomial,
+	pub divisor: BigInt,
+}
+
+/// How the divisibility turned out in the cases of a case split.
+pub struct Cases {
+	pub count: u64,
+	/// the first residues of the unknowns for which it doesn't hold
+	pub failing: Option<Vec<(String, BigInt)>>,
+}
+
+impl Divisibility {
+	/// Returns `None` if the divisibility isn't of that form, where
+	/// `is_integer` tells whether an unknown only takes integer values.
+	pub fn new(expr: &ExprNode, divisor: &ExprNode, is_integer: impl Fn(&str) -> bool) -> Option<Self> {
+		let divisor = evaluate(divisor)?.to_integer().filter(|d| !d.is_zero())?.abs();
+		let poly = Polynomial::from_expr(expr)?;
+		if poly.is_integral(is_integer) { Some(Self { poly, divisor }) } else { None }
+	}
+
+	/// The greatest common divisor of all coefficients and the divisor.
+	fn common_factor(&self) -> BigInt {
+		self.poly.coefficients()
+			.fold(self.divisor.clone(), |g, c| g.gcd(&c.to_integer()))
+	}
+
+	/// Divides both sides by their common factor, if they have one.
+	pub fn factor_out(&self) -> Option<(BigInt, Divisibility)> {
+		let g = self.common_factor();
+		if g.is_one() { return None; }
+
+		let factor = BigRational::from_integer(g.clone());
+		Some((g.clone(), Divisibility {
+			poly: self.poly.map_coefficients(|c| c / &factor),
+			divisor: &self.divisor / &g,
+		}))
+	}
+
+	/// Reduces every coefficient modulo the divisor, if any of them
+	/// isn't reduced yet. Multiples of the divisor are dropped entirely.
+	pub fn reduce(&self) -> Option<Polynomial> {
+		let d = BigRational::from_integer(self.divisor.clone());
+		let reduced = self.poly.map_coefficients(|c| BigRational::from_integer(c.to_integer().mod_floor(d.numer())));
+		if reduced == self.poly { None } else { Some(reduced) }
+	}
+
+	/// Checks the divisibility for every residue of the unknowns modulo
+	/// the divisor, which is all values matter for. Returns `None` if
+	/// there are too many cases.
+	pub fn cases(&self) -> Option<Cases> {
+		let names: Vec<&String> = self.poly.unknowns().keys().collect();
+		let d = self.divisor.to_u64()?;
+		let count = d.checked_pow(names.len().try_into().ok()?).filter(|&c| c <= MAX_CASES)?;
+
+		let mut residues = vec![0u64; names.len()];
+		for _ in 0..count {
+			let values: BTreeMap<String, BigRational> = names.iter().zip(&residues)
+				.map(|(n, &r)| ((*n).clone(), BigRational::from_integer(r.into())))
+				.collect();
+
+			let value = self.poly.evaluate(&values)?.to_integer();
+			if !value.mod_floor(&self.divisor).is_zero() {
+				let failing = values.into_iter().map(|(n, v)| (n, v.to_integer())).collect();
+				return Some(Cases { count, failing: Some(failing) });
+			}
+
+			// the next combination of residues
+			for r in residues.iter_mut().rev() {
+				*r += 1;
+				if *r < d { break; }
+				*r = 0;
+			}
+		}
+
+		Some(Cases { count, failing: None })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::parse;
+
+	/// The divisibility of `expr` by `divisor`, in the integer `n` and the real `x`.
+	fn divisibility(expr: &str, divisor: &str) -> Option<Divisibility> {
+		let script = parse(&format!("$n: int\n$x: real\n$e := {}\n$d := {}", expr, divisor));
+		Divisibility::new(script.variable("e"), script.variable("d"), |name| name == "n")
+	}
+
+	fn polynomial(expr: &str) -> Polynomial {
+		divisibility(expr, "1").unwrap().poly
+	}
+
+	#[test]
+	fn only_integer_polynomials_by_constants() {
+		assert!(divisibility("3 n^2 + 1", "2").is_some());
+		assert!(divisibility("x + 1", "2").is_none());
+		assert!(divisibility("n / 2", "2").is_none());
+		assert!(divisibility("n", "0").is_none());
+		assert!(divisibility("n", "n").is_none());
+	}
+
+	#[test]
+	fn factors_out_common_factors() {
+		let (factor, rest) = divisibility("6 n + 4", "4").unwrap().factor_out().unwrap();
+		assert_eq!(factor, BigInt::from(2));
+		assert_eq!(rest.divisor, BigInt::from(2));
+		assert_eq!(rest.poly, polynomial("3 n + 2"));
+		assert!(rest.factor_out().is_none());
+	}
+
+	#[test]
+	fn reduces_coefficients() {
+		assert_eq!(divisibility("3 n + 2", "2").unwrap().reduce(), Some(polynomial("n")));
+		assert_eq!(divisibility("n + 1", "2").unwrap().reduce(), None);
+	}
+
+	#[test]
+	fn splits_on_residues() {
+		let cases = divisibility("n^2 + n", "2").unwrap().cases().unwrap();
+		assert_eq!(cases.count, 2);
+		assert!(cases.failing.is_none());
+
+		let cases = divisibility("n^2", "4").unwrap().cases().unwrap();
+		assert_eq!(cases.failing, Some(vec![("n".to_string(), BigInt::from(1))]));
+	}
+}
@@ -0,0 +1,262 @@
+use super::rewrite::{Rewrite, children, with_children, regroup};
+use crate::parse::{ast::*, astprinter::ExprPrinter};
+use std::collections::{HashMap, VecDeque};
+
+/// Why two e-nodes were put in the same class.
+#[derive(Clone, Debug)]
+enum Reason {
+	/// the first node was rewritten into the second one
+	Rewrite(Box<Rewrite>),
+	/// both nodes apply the same operator to equivalent children
+	Congruence,
+}
+
+struct ENode {
+	/// the operator of the node, without its children
+	op: String,
+	/// the nodes of the children the node was built from
+	children: Vec<usize>,
+	/// the expression the node was built from
+	term: ExprNode,
+}
+
+/// An e-graph: a set of expressions grouped into classes of equivalent
+/// expressions, sharing common sub-expressions. Every node remembers the
+/// expression it was built from, and every merge of two classes remembers
+/// why, so that the equivalence of two nodes can be explained step by step.
+#[derive(Default)]
+pub struct EGraph {
+	nodes: Vec<ENode>,
+	parents: Vec<usize>,
+	memo: HashMap<(String, Vec<usize>), usize>,
+	/// the merges, which form a forest over the nodes
+	edges: Vec<(usize, usize, Reason)>,
+	adjacent: Vec<Vec<usize>>,
+}
+
+fn strip_grouping(expr: &ExprNode) -> &ExprNode {
+	match &expr.item {
+		ExprItem::Grouping(e) => strip_grouping(e),
+		_ => expr,
+	}
+}
+
+fn op(expr: &ExprNode) -> String {
+	//! the parts of an expression that aren't its children
+	match &expr.item {
+		ExprItem::Equality { .. } |
+		ExprItem::Term { .. } |
+		ExprItem::Factor { .. } |
+		ExprItem::Unary(_) |
+		ExprItem::Power { .. } => format!("{:?}", expr.token.kind),
+		ExprItem::Call { path, .. } => format!("{}()", path),
+		ExprItem::Builtin { builtin, .. } => format!("{}()", builtin.name),
+		_ => ExprPrinter::print(expr),
+	}
+}
+
+impl EGraph {
+	/// The number of nodes, which the solver counts as its states.
+	pub(crate) fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn term(&self, node: usize) -> &ExprNode {
+		&self.nodes[node].term
+	}
+
+	pub fn children(&self, node: usize) -> &[usize] {
+		&self.nodes[node].children
+	}
+
+	pub fn find(&self, mut node: usize) -> usize {
+		while self.parents[node] != node { node = self.parents[node]; }
+		node
+	}
+
+	/// Adds an expression and all of its sub-expressions, returning its node.
+	pub fn add(&mut self, expr: &ExprNode) -> usize {
+		let expr = strip_grouping(expr);
+		let children: Vec<usize> = children(expr).into_iter().map(|c| self.add(c)).collect();
+		let key = (op(expr), children);
+
+		if let Some(&node) = self.memo.get(&key) {
+			return node;
+		}
+
+		let node = self.nodes.len();
+		self.nodes.push(ENode { op: key.0.clone(), children: key.1.clone(), term: regroup(expr) });
+		self.parents.push(node);
+		self.adjacent.push(Vec::new());
+		self.memo.insert(key, node);
+		node
+	}
+
+	fn union(&mut self, a: usize, b: usize, reason: Reason) -> bool {
+		let (ra, rb) = (self.find(a), self.find(b));
+		if ra == rb { return false; }
+
+		self.parents[ra] = rb;
+		self.edges.push((a, b, reason));
+		self.adjacent[a].push(self.edges.len() - 1);
+		self.adjacent[b].push(self.edges.len() - 1);
+		true
+	}
+
+	/// Puts a node in the same class as the node it was rewritten
+	/// into. Returns `false` if they already were in the same class.
+	pub fn union_rewrite(&mut self, from: usize, to: usize, rewrite: Rewrite) -> bool {
+		self.union(from, to, Reason::Rewrite(Box::new(rewrite)))
+	}
+
+	/// Merges the classes of nodes that apply the same operator to
+	/// children of the same classes, until there are no such nodes left.
+	pub fn rebuild(&mut self) {
+		loop {
+			let mut canonical: HashMap<(&str, Vec<usize>), usize> = HashMap::new();
+			let mut merges = Vec::new();
+
+			for (i, node) in self.nodes.iter().enumerate() {
+				let key = (node.op.as_str(), node.children.iter().map(|&c| self.find(c)).collect());
+				match canonical.get(&key) {
+					Some(&other) if self.find(other) != self.find(i) => merges.push((other, i)),
+					Some(_) => {},
+					None => { canonical.insert(key, i); },
+				}
+			}
+
+			let mut changed = false;
+			for (a, b) in merges {
+				changed |= self.union(a, b, Reason::Congruence);
+			}
+			if !changed { break; }
+		}
+	}
+
+	/// Returns the members of every class, by the root of the class.
+	pub fn classes(&self) -> HashMap<usize, Vec<usize>> {
+		let mut classes: HashMap<usize, Vec<usize>> = HashMap::new();
+		for i in 0..self.nodes.len() {
+			classes.entry(self.find(i)).or_default().push(i);
+		}
+		classes
+	}
+
+	/// The merges leading from `a` to `b`, each as `(from, to, reason)`,
+	/// along with whether they're walked in the direction they were made.
+	fn path(&self, a: usize, b: usize) -> Vec<(usize, usize, bool, &Reason)> {
+		// the merges form a forest, so there's only one path
+		let mut previous: HashMap<usize, usize> = HashMap::from([(a, usize::MAX)]);
+		let mut queue = VecDeque::from([a]);
+
+		while let Some(node) = queue.pop_front() {
+			if node == b { break; }
+			for &edge in &self.adjacent[node] {
+				let (x, y, _) = &self.edges[edge];
+				let next = if *x == node { *y } else { *x };
+				if let std::collections::hash_map::Entry::Vacant(e) = previous.entry(next) {
+					e.insert(edge);
+					queue.push_back(next);
+				}
+			}
+		}
+
+		let mut path = Vec::new();
+		let mut node = b;
+		while node != a {
+			let (x, y, reason) = &self.edges[previous[&node]];
+			let from = if *x == node { *y } else { *x };
+			path.push((from, node, *x == from, reason));
+			node = from;
+		}
+		path.reverse();
+		path
+	}
+
+	/// Explains why two nodes of the same class are equivalent, as the
+	/// rewrites that turn the expression of `a` into that of `b`, each
+	/// along with the whole expression after it.
+	pub fn explain(&self, a: usize, b: usize) -> Vec<(Rewrite, ExprNode)> {
+		let mut steps = Vec::new();
+
+		for (from, to, forward, reason) in self.path(a, b) {
+			match reason {
+				Reason::Rewrite(rewrite) if forward => {
+					steps.push((*rewrite.clone(), self.term(to).clone()));
+				},
+				Reason::Rewrite(rewrite) => {
+					let reversed = Rewrite {
+						description: format!("{} (reversed)", rewrite.description),
+						before: rewrite.after.clone(),
+						after: rewrite.before.clone(),
+						conditions: rewrite.conditions.clone(),
+					};
+					steps.push((reversed, self.term(to).clone()));
+				},
+				Reason::Congruence => {
+					// rewrite the children one by one
+					let (old, new) = (self.children(from), self.children(to));
+					let mut current: Vec<ExprNode> = old.iter().map(|&c| self.term(c).clone()).collect();
+
+					for (i, (&c, &d)) in old.iter().zip(new).enumerate() {
+						for (rewrite, child) in self.explain(c, d) {
+							current[i] = child;
+							steps.push((rewrite, regroup(&with_children(self.term(from), current.clone()))));
+						}
+					}
+				},
+			}
+		}
+
+		steps
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::parse;
+
+	const SCRIPT: &str = "$a: real\n$b: real\n$x := a\n$y := b\n$s := (a + 1) * 2\n$t := (b + 1) * 2";
+
+	#[test]
+	fn shares_common_subexpressions() {
+		let script = parse(SCRIPT);
+		let mut egraph = EGraph::default();
+		let s = egraph.add(script.variable("s"));
+		// a, 1, a + 1, 2 and the product
+		assert_eq!(egraph.len(), 5);
+		assert_eq!(egraph.add(script.variable("s")), s);
+		egraph.add(script.variable("t"));
+		assert_eq!(egraph.len(), 8);
+	}
+
+	#[test]
+	fn merging_children_merges_their_parents() {
+		let script = parse(SCRIPT);
+		let mut egraph = EGraph::default();
+		let (s, t) = (egraph.add(script.variable("s")), egraph.add(script.variable("t")));
+		let (a, b) = (egraph.add(script.variable("x")), egraph.add(script.variable("y")));
+		assert_ne!(egraph.find(s), egraph.find(t));
+
+		let rewrite = Rewrite {
+			description: String::from("rename"),
+			before: script.variable("x").clone(),
+			after: script.variable("y").clone(),
+			conditions: vec![],
+		};
+		assert!(egraph.union_rewrite(a, b, rewrite));
+		egraph.rebuild();
+		assert_eq!(egraph.find(s), egraph.find(t));
+
+		// the rename, applied inside the product
+		let steps = egraph.explain(s, t);
+		assert_eq!(steps.len(), 1);
+		assert_eq!(steps[0].0.description, "rename");
+		assert_eq!(&steps[0].1, script.variable("t"));
+
+		let back = egraph.explain(t, s);
+		assert_eq!(back[0].0.description, "rename (reversed)");
+		assert_eq!(&back[0].1, script.variable("s"));
+	}
+}
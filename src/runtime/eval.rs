@@ -0,0 +1,313 @@
+use super::interval::compare;
+use crate::{
+	parse::ast::{ExprNode, ExprItem, Literal},
+	lex::token::{Token, TokenKind::*},
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, FromPrimitive, Zero};
+use num_integer::Integer as _;
+
+/// The largest number of bits an exact power may have,
+/// anything larger is left unevaluated.
+const MAX_POWER_BITS: u64 = 1 << 16;
+
+/// A number is either exact (an arbitrarily large integer or rational)
+/// or an approximation, which is what decimal literals evaluate to.
+#[derive(Clone, Debug)]
+pub enum Number {
+	Exact(BigRational),
+	Approx(f64),
+}
+
+impl Number {
+	pub fn integer(v: impl Into<BigInt>) -> Self {
+		Number::Exact(BigRational::from_integer(v.into()))
+	}
+
+	pub fn approx(v: f64) -> Option<Self> {
+		//! infinities and NaNs aren't numbers
+		if v.is_finite() { Some(Number::Approx(v)) } else { None }
+	}
+
+	pub fn is_approx(&self) -> bool {
+		matches!(self, Number::Approx(_))
+	}
+
+	pub fn is_integer(&self) -> bool {
+		matches!(self, Number::Exact(v) if v.is_integer())
+	}
+
+	pub fn to_integer(&self) -> Option<BigInt> {
+		match self {
+			Number::Exact(v) if v.is_integer() => Some(v.to_integer()),
+			_ => None,
+		}
+	}
+
+	pub fn to_f64(&self) -> f64 {
+		match self {
+			Number::Exact(v) => v.to_f64().unwrap_or(f64::NAN),
+			Number::Approx(v) => *v,
+		}
+	}
+
+	pub fn is_zero(&self) -> bool {
+		match self {
+			Number::Exact(v) => v.is_zero(),
+			Number::Approx(v) => *v == 0.0,
+		}
+	}
+
+	pub fn is_negative(&self) -> bool {
+		match self {
+			Number::Exact(v) => v.is_negative(),
+			Number::Approx(v) => *v < 0.0,
+		}
+	}
+
+	pub fn from_literal(literal: &Literal) -> Self {
+		match literal {
+			Literal::Integer(v) => Number::integer(v.clone()),
+			Literal::Float(v) => Number::Approx(*v),
+		}
+	}
+
+	pub fn from_expr(expr: &ExprNode) -> Option<Self> {
+		//! only accepts (negated) literals and fractions of integer literals
+		match &expr.item {
+			ExprItem::Literal(l) => Some(Self::from_literal(l)),
+			ExprItem::Grouping(e) => Self::from_expr(e),
+			ExprItem::Unary(e) => match &e.item {
+				ExprItem::Literal(l) => Self::from_literal(l).checked_neg(),
+				_ => None,
+			},
+			ExprItem::Factor { lhs, rhs, implicit: false } if expr.token.kind == Divide => {
+				let numer = Self::from_expr(lhs)?.to_integer()?;
+				match &rhs.item {
+					ExprItem::Literal(Literal::Integer(denom)) if !denom.is_zero() =>
+						Some(Number::Exact(BigRational::new(numer, denom.clone()))),
+					_ => None,
+				}
+			},
+			_ => None,
+		}
+	}
+
+	pub fn to_expr(&self) -> Option<ExprNode> {
+		//! the inverse of `from_expr`, fractions become a division
+		let literal = |literal, kind| ExprNode { token: Token::synthetic(kind), item: ExprItem::Literal(literal) };
+		let negate = |node| ExprNode { token: Token::synthetic(Minus), item: ExprItem::Unary(Box::new(node)) };
+
+		let (negative, node) = match self {
+			Number::Exact(v) if v.is_integer() => (v.is_negative(), literal(Literal::Integer(v.numer().abs()), Integer)),
+			Number::Exact(v) => {
+				let numer = literal(Literal::Integer(v.numer().abs()), Integer);
+				let numer = if v.is_negative() { negate(numer) } else { numer };
+				let denom = literal(Literal::Integer(v.denom().clone()), Integer);
+				return Some(ExprNode {
+					token: Token::synthetic(Divide),
+					item: ExprItem::Factor { lhs: Box::new(numer), rhs: Box::new(denom), implicit: false },
+				});
+			},
+			Number::Approx(v) if v.is_finite() => (*v < 0.0, literal(Literal::Float(v.abs()), Float)),
+			Number::Approx(_) => return None,
+		};
+
+		Some(if negative { negate(node) } else { node })
+	}
+}
+
+// arithmetic
+impl Number {
+	/// Applies an operation exactly if both numbers are
+	/// exact, and approximately if either of them isn't.
+	fn combine(&self, rhs: &Self, exact: fn(&BigRational, &BigRational) -> Option<BigRational>, approx: fn(f64, f64) -> f64) -> Option<Self> {
+		match (self, rhs) {
+			(Number::Exact(a), Number::Exact(b)) => exact(a, b).map(Number::Exact),
+			(a, b) => Number::approx(approx(a.to_f64(), b.to_f64())),
+		}
+	}
+
+	pub fn checked_neg(&self) -> Option<Self> {
+		match self {
+			Number::Exact(v) => Some(Number::Exact(-v)),
+			Number::Approx(v) => Some(Number::Approx(-v)),
+		}
+	}
+
+	pub fn checked_add(&self, rhs: &Self) -> Option<Self> {
+		self.combine(rhs, |a, b| Some(a + b), |a, b| a + b)
+	}
+
+	pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+		self.combine(rhs, |a, b| Some(a - b), |a, b| a - b)
+	}
+
+	pub fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+		self.combine(rhs, |a, b| Some(a * b), |a, b| a * b)
+	}
+
+	pub fn checked_div(&self, rhs: &Self) -> Option<Self> {
+		if rhs.is_zero() { return None; }
+		self.combine(rhs, |a, b| Some(a / b), |a, b| a / b)
+	}
+
+	pub fn checked_pow(&self, rhs: &Self) -> Option<Self> {
+		//! stays exact for integer exponents that don't blow up the result
+		if let (Number::Exact(base), Some(exp)) = (self, rhs.to_integer()) {
+			let bits = base.numer().bits() + base.denom().bits();
+			let exp = exp.to_i32()?;
+			if bits.saturating_mul(exp.unsigned_abs() as u64) > MAX_POWER_BITS { return None; }
+			if base.is_zero() && exp < 0 { return None; }
+			return Some(Number::Exact(base.pow(exp)));
+		}
+		Number::approx(self.to_f64().powf(rhs.to_f64()))
+	}
+
+	pub fn checked_rem(&self, rhs: &Self) -> Option<Self> {
+		//! only defined for integers
+		let (a, b) = (self.to_integer()?, rhs.to_integer()?);
+		if b.is_zero() { return None; }
+		Some(Number::integer(a.mod_floor(&b.abs())))
+	}
+
+	pub fn is_multiple_of(&self, rhs: &Self) -> Option<bool> {
+		//! whether dividing by `rhs` gives an integer, which a fraction never does
+		match (self, rhs) {
+			(Number::Exact(a), Number::Exact(b)) if !b.is_zero() => Some((a / b).is_integer()),
+			_ => Some(self.checked_rem(rhs)?.is_zero()),
+		}
+	}
+
+	pub fn from_f64_integer(v: f64) -> Option<Self> {
+		//! the exact integer value of a whole float
+		BigInt::from_f64(v).map(Number::integer)
+	}
+}
+
+impl PartialEq for Number {
+	fn eq(&self, other: &Self) -> bool {
+		match (self, other) {
+			(Number::Exact(a), Number::Exact(b)) => a == b,
+			(a, b) => a.to_f64() == b.to_f64(),
+		}
+	}
+}
+
+impl PartialOrd for Number {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		match (self, other) {
+			(Number::Exact(a), Number::Exact(b)) => a.partial_cmp(b),
+			(a, b) => a.to_f64().partial_cmp(&b.to_f64()),
+		}
+	}
+}
+
+impl std::fmt::Display for Number {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Number::Exact(v) => write!(f, "{}", v),
+			Number::Approx(v) => write!(f, "{}", v),
+		}
+	}
+}
+
+/// Evaluates a closed expression.
+/// Returns `None` if the expression contains anything that
+/// can't be evaluated yet (such as unsubstituted variables).
+pub fn evaluate(expr: &ExprNode) -> Option<Number> {
+	match &expr.item {
+		ExprItem::Literal(l) => Some(Number::from_literal(l)),
+		ExprItem::Grouping(e) => evaluate(e),
+		ExprItem::Unary(e) => evaluate(e)?.checked_neg(),
+		ExprItem::Term { lhs, rhs } => match expr.token.kind {
+			Plus => evaluate(lhs)?.checked_add(&evaluate(rhs)?),
+			Minus => evaluate(lhs)?.checked_sub(&evaluate(rhs)?),
+			_ => unreachable!(),
+		},
+		ExprItem::Factor { lhs, rhs, .. } => match expr.token.kind {
+			Multiply => evaluate(lhs)?.checked_mul(&evaluate(rhs)?),
+			Divide => evaluate(lhs)?.checked_div(&evaluate(rhs)?),
+			_ => unreachable!(),
+		},
+		ExprItem::Power { base, power } => evaluate(base)?.checked_pow(&evaluate(power)?),
+		ExprItem::Builtin { builtin, args, .. } => {
+			let args = args.iter().map(evaluate).collect::<Option<Vec<Number>>>()?;
+			(builtin.eval)(&args)
+		},
+		ExprItem::Equality { .. } |
+		ExprItem::Variable { .. } |
+		ExprItem::Call { .. } |
+		ExprItem::Symbol(_) |
+		ExprItem::Generic(_) => None,
+	}
+}
+
+/// Evaluates a closed equation (`a = b` or `a /= b`) to a truth value,
+/// if rounding doesn't keep it from being told.
+pub fn evaluate_equation(expr: &ExprNode) -> Option<bool> {
+	match &expr.item {
+		ExprItem::Grouping(e) => evaluate_equation(e),
+		ExprItem::Equality { lhs, rhs } => {
+			let equal = compare(lhs, rhs)?.is_eq();
+			Some(if expr.token.kind == Equals { equal } else { !equal })
+		},
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::{parse, fails_to_parse};
+
+	fn value(expr: &str) -> Option<Number> {
+		evaluate(parse(&format!("$e := {}", expr)).variable("e"))
+	}
+
+	fn ratio(numer: i64, denom: i64) -> Number {
+		Number::Exact(BigRational::new(numer.into(), denom.into()))
+	}
+
+	#[test]
+	fn fractions_are_exact() {
+		assert_eq!(value("1 / 3 + 1 / 6"), Some(ratio(1, 2)));
+		assert!(!value("1 / 3 + 1 / 6").unwrap().is_approx());
+		assert_eq!(value("(2 / 3)^-2"), Some(ratio(9, 4)));
+	}
+
+	#[test]
+	fn integers_are_arbitrarily_large() {
+		let big = BigInt::from(2).pow(100u32);
+		assert_eq!(value("2^100"), Some(Number::integer(big.clone())));
+		assert_eq!(value("1267650600228229401496703205376"), Some(Number::integer(big)));
+	}
+
+	#[test]
+	fn decimals_are_approximate() {
+		assert!(value("0.5").unwrap().is_approx());
+		assert_eq!(value("0.5 + 1 / 2"), Some(Number::integer(1)));
+	}
+
+	#[test]
+	fn literals_in_other_bases() {
+		assert_eq!(value("0c17"), Some(Number::integer(15)));
+		assert_eq!(value("0x1F"), Some(Number::integer(31)));
+		assert_eq!(value("0b101"), Some(Number::integer(5)));
+		assert!(fails_to_parse("$e := 0c8"));
+	}
+
+	#[test]
+	fn undefined_operations_have_no_value() {
+		assert_eq!(value("1 / 0"), None);
+		assert_eq!(value("0^-1"), None);
+		assert_eq!(Number::integer(7).checked_rem(&Number::integer(-3)), Some(Number::integer(1)));
+		assert_eq!(ratio(1, 2).checked_rem(&Number::integer(2)), None);
+	}
+
+	#[test]
+	fn overflowing_decimals_are_reported() {
+		assert!(fails_to_parse(&format!("$e := 1{}.5", "0".repeat(400))));
+	}
+}
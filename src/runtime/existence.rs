@@ -0,0 +1,299 @@
+use super::{
+	eval::{Number, evaluate_equation},
+	polynomial::Polynomial,
+	rewrite::children,
+	soundness::{Assignment, format_assignment, free_unknowns, integer_points, value_at, compare_at},
+	types::Type,
+};
+use crate::{
+	parse::ast::{ExprNode, ExprItem},
+	lex::token::TokenKind::{Equals, NotEquals},
+};
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+use std::collections::BTreeMap;
+
+/// How many integer points are tried before looking for a sign change.
+const MAX_POINTS: usize = 1_000;
+/// The bounds of the interval that's scanned for sign changes,
+/// and how many steps each unit of it is split into.
+const MAX_SCAN: i32 = 100;
+const SCAN_STEPS: i32 = 8;
+/// How often an interval with a sign change is halved.
+const BISECTIONS: usize = 64;
+/// How close to zero the difference of the sides has to be at a root.
+const TOLERANCE: f64 = 1e-9;
+
+/// Values of the unknowns for which an equation holds, each written as
+/// `x = 2`. Either all of them, or just one (the witness) if there may be
+/// others.
+#[derive(Clone, Debug, Default)]
+pub struct Solutions {
+	pub values: Vec<String>,
+	pub complete: bool,
+}
+
+/// Whether an equation has a solution, and why.
+pub struct Existence {
+	pub exists: bool,
+	pub conclusion: String,
+	pub solutions: Solutions,
+}
+
+/// A root of a polynomial of at most the second degree.
+enum Root {
+	Rational(BigRational),
+	/// `p + sqrt(r)`, or `p - sqrt(r)` if `negative` is set
+	Irrational { p: BigRational, r: BigRational, negative: bool },
+}
+
+impl Root {
+	fn format(&self, name: &str) -> String {
+		match self {
+			Root::Rational(v) => format!("{} = {}", name, v),
+			Root::Irrational { p, r, negative } if p.is_zero() => format!("{} = {}sqrt({})", name, if *negative { "-" } else { "" }, r),
+			Root::Irrational { p, r, negative } => format!("{} = {} {} sqrt({})", name, p, if *negative { '-' } else { '+' }, r),
+		}
+	}
+}
+
+fn strip_grouping(expr: &ExprNode) -> &ExprNode {
+	match &expr.item {
+		ExprItem::Grouping(e) => strip_grouping(e),
+		_ => expr,
+	}
+}
+
+/// The square root of a rational number, if it's rational itself.
+pub fn rational_sqrt(r: &BigRational) -> Option<BigRational> {
+	let (n, d) = (r.numer().sqrt(), r.denom().sqrt());
+	if &(&n * &n) == r.numer() && &(&d * &d) == r.denom() { Some(BigRational::new(n, d)) } else { None }
+}
+
+fn is_substituted(expr: &ExprNode) -> bool {
+	!matches!(expr.item, ExprItem::Call { .. } | ExprItem::Variable { .. }) && children(expr).into_iter().all(is_substituted)
+}
+
+fn witness(kind: &str, assignment: &Assignment) -> Existence {
+	let values = format_assignment(assignment);
+	Existence {
+		exists: true,
+		conclusion: format!("lhs {} rhs when {}!", kind, values),
+		solutions: Solutions { values: vec![values], complete: false },
+	}
+}
+
+/// Decides whether an equation (`a = b` or `a /= b`) holds for some values
+/// of its unknowns. Equations that are linear or quadratic in a single
+/// unknown are solved exactly, others are tried at small integers and,
+/// in a single real unknown, scanned for a change of sign.
+/// `annotation` gives the type of an unknown, if it has one.
+pub fn solve(expr: &ExprNode, annotation: impl Fn(&str) -> Option<Type>) -> Option<Existence> {
+	let expr = strip_grouping(expr);
+	let ExprItem::Equality { lhs, rhs } = &expr.item else { return None };
+
+	if expr.token.kind == Equals {
+		if let Some(existence) = solve_polynomial(lhs, rhs, &annotation) {
+			return Some(existence);
+		}
+	}
+	search(expr, lhs, rhs, &annotation)
+}
+
+fn solve_polynomial(lhs: &ExprNode, rhs: &ExprNode, annotation: &impl Fn(&str) -> Option<Type>) -> Option<Existence> {
+	let p = Polynomial::from_expr(lhs)?.sub(&Polynomial::from_expr(rhs)?);
+
+	if p.is_zero() {
+		return Some(Existence {
+			exists: true,
+			conclusion: String::from(if p.unknowns().is_empty() { "lhs equals rhs!" } else { "lhs equals rhs for any values!" }),
+			solutions: Solutions::default(),
+		});
+	}
+	if let Some(c) = p.as_constant() {
+		return Some(Existence {
+			exists: false,
+			conclusion: format!("lhs and rhs always differ by {}!", c),
+			solutions: Solutions { values: vec![], complete: true },
+		});
+	}
+
+	let (name, c) = p.univariate()?;
+	let mut roots = match c.as_slice() {
+		[c0, c1] => vec![Root::Rational(-c0 / c1)],
+		[c0, c1, c2] => {
+			let discriminant = c1 * c1 - BigRational::from_integer(4.into()) * c2 * c0;
+			if discriminant.is_negative() {
+				return Some(Existence {
+					exists: false,
+					conclusion: format!("lhs never equals rhs, as the discriminant {} is negative!", discriminant),
+					solutions: Solutions { values: vec![], complete: true },
+				});
+			}
+
+			// x = p ± sqrt(r), with p = -b / 2a and r = D / 4a^2
+			let two_a = c2 * BigRational::from_integer(2.into());
+			let p = -c1 / &two_a;
+			let r = discriminant / (&two_a * &two_a);
+			match rational_sqrt(&r) {
+				Some(s) if s.is_zero() => vec![Root::Rational(p)],
+				Some(s) => vec![Root::Rational(&p - &s), Root::Rational(&p + s)],
+				None => vec![
+					Root::Irrational { p: p.clone(), r: r.clone(), negative: true },
+					Root::Irrational { p, r, negative: false },
+				],
+			}
+		},
+		_ => return None,
+	};
+
+	if annotation(&name) == Some(Type::Int) {
+		roots.retain(|root| matches!(root, Root::Rational(v) if v.is_integer()));
+	}
+	let values: Vec<String> = roots.iter().map(|root| root.format(&name)).collect();
+
+	Some(Existence {
+		exists: !values.is_empty(),
+		conclusion: match values.is_empty() {
+			true => format!("lhs never equals rhs for an integer {}!", name),
+			false => format!("lhs equals rhs exactly when {}!", values.join(" or ")),
+		},
+		solutions: Solutions { values, complete: true },
+	})
+}
+
+fn search(expr: &ExprNode, lhs: &ExprNode, rhs: &ExprNode, annotation: &impl Fn(&str) -> Option<Type>) -> Option<Existence> {
+	let mut names = free_unknowns(lhs);
+	names.extend(free_unknowns(rhs));
+
+	if names.is_empty() {
+		let exists = evaluate_equation(expr)?;
+		return Some(Existence {
+			exists,
+			conclusion: format!("the equation evaluates to {}!", exists),
+			solutions: Solutions { values: vec![], complete: true },
+		});
+	}
+	// calls and variables are substituted by the solver first
+	if !is_substituted(expr) { return None; }
+
+	let sides = |values: &BTreeMap<String, Number>| Some((value_at(lhs, values)?, value_at(rhs, values)?));
+	let negate = expr.token.kind == NotEquals;
+	let kind = if negate { "differs from" } else { "equals" };
+
+	let points = integer_points(names.len(), MAX_POINTS).into_iter()
+		.map(|point| names.iter().cloned().zip(point.into_iter().map(Number::integer)).collect::<BTreeMap<String, Number>>());
+	for values in points {
+		// a witness has to be one for certain, whatever the rounding
+		if compare_at(lhs, rhs, &values).is_some_and(|order| order.is_eq() != negate) {
+			return Some(witness(kind, &values.into_iter().collect()));
+		}
+	}
+
+	// an equation in a single real unknown has a root where
+	// the difference of its sides changes sign, if it's continuous
+	let name = names.first()?;
+	if negate || names.len() > 1 || annotation(name) == Some(Type::Int) { return None; }
+
+	let difference = |x: f64| {
+		let (a, b) = sides(&BTreeMap::from([(name.clone(), Number::approx(x)?)]))?;
+		Some(a.to_f64() - b.to_f64())
+	};
+	let grid = (-MAX_SCAN * SCAN_STEPS..=MAX_SCAN * SCAN_STEPS).map(|i| i as f64 / SCAN_STEPS as f64);
+	let samples: Vec<(f64, f64)> = grid.filter_map(|x| Some((x, difference(x)?))).collect();
+
+	let near = |root: f64| {
+		let values = format!("{} ≈ {}", name, root);
+		Existence {
+			exists: true,
+			conclusion: format!("lhs equals rhs near {}, where their difference changes sign!", values),
+			solutions: Solutions { values: vec![values], complete: false },
+		}
+	};
+	if let Some(&(x, _)) = samples.iter().find(|(_, d)| *d == 0.0) {
+		return Some(near(x));
+	}
+
+	for pair in samples.windows(2) {
+		let ((mut a, fa), (mut b, fb)) = (pair[0], pair[1]);
+		if fa.signum() == fb.signum() { continue; }
+
+		for _ in 0..BISECTIONS {
+			let m = (a + b) / 2.0;
+			match difference(m) {
+				Some(fm) if fm.signum() == fa.signum() => a = m,
+				Some(_) => b = m,
+				None => break,
+			}
+		}
+
+		// a jump across zero, such as that of 1 / x, isn't a root
+		let root = (a + b) / 2.0;
+		if difference(root).is_some_and(|d| d.abs() <= TOLERANCE * root.abs().max(1.0)) {
+			return Some(near(root));
+		}
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::parse;
+
+	/// Whether the equation has a solution in `x`, which is an integer if
+	/// `int` is set, along with its solutions.
+	fn solutions(equation: &str, int: bool) -> Option<(bool, Vec<String>, bool)> {
+		let script = parse(&format!("$x: real\n$y: real\n$e := {}", equation));
+		let existence = solve(script.variable("e"), |_| int.then_some(Type::Int))?;
+		Some((existence.exists, existence.solutions.values, existence.solutions.complete))
+	}
+
+	fn values(values: &[&str]) -> Vec<String> {
+		values.iter().map(|v| v.to_string()).collect()
+	}
+
+	#[test]
+	fn solves_linear_and_quadratic_equations() {
+		assert_eq!(solutions("2 x + 1 = 0", false), Some((true, values(&["x = -1/2"]), true)));
+		assert_eq!(solutions("x^2 - 4 = 0", false), Some((true, values(&["x = -2", "x = 2"]), true)));
+		assert_eq!(solutions("x^2 = 2", false), Some((true, values(&["x = -sqrt(2)", "x = sqrt(2)"]), true)));
+		assert_eq!(solutions("x^2 + 1 = 0", false), Some((false, vec![], true)));
+	}
+
+	#[test]
+	fn integers_only_have_integer_solutions() {
+		assert_eq!(solutions("2 x = 1", true), Some((false, vec![], true)));
+		assert_eq!(solutions("x^2 - x = 2", true), Some((true, values(&["x = -1", "x = 2"]), true)));
+	}
+
+	#[test]
+	fn identities_and_contradictions() {
+		assert_eq!(solutions("x + y = y + x", false), Some((true, vec![], false)));
+		assert_eq!(solutions("x + 1 = x", false), Some((false, vec![], true)));
+	}
+
+	#[test]
+	fn rounding_errors_are_not_witnesses() {
+		assert_eq!(solutions("sqrt(x)^2 /= x", false), None);
+		assert_eq!(solutions("sqrt(x) = 3", false), Some((true, values(&["x = 9"]), false)));
+	}
+
+	#[test]
+	fn other_equations_are_searched() {
+		// a witness at a small integer
+		assert_eq!(solutions("x y = 6", false), Some((true, values(&["x = -3, y = -2"]), false)));
+		// a root between the integers
+		let (exists, values, complete) = solutions("x^3 = 3", false).unwrap();
+		assert!(exists && !complete);
+		assert!(values[0].starts_with("x ≈ 1.44"));
+	}
+
+	#[test]
+	fn square_roots_of_rationals() {
+		let ratio = |n: i64, d: i64| BigRational::new(n.into(), d.into());
+		assert_eq!(rational_sqrt(&ratio(9, 4)), Some(ratio(3, 2)));
+		assert_eq!(rational_sqrt(&ratio(2, 1)), None);
+	}
+}
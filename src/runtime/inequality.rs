@@ -0,0 +1,301 @@
+use super::{
+	eval::Number,
+	polynomial::{Polynomial, node, implicit},
+	rewrite::regroup,
+};
+use crate::{
+	parse::ast::{ExprNode, ExprItem, Literal},
+	lex::token::TokenKind::{self, *},
+};
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+/// A bound on the values of an expression, which is
+/// either reached (`<=`, `>=`) or not (`<`, `>`).
+#[derive(Clone, Debug)]
+struct Bound {
+	value: BigRational,
+	strict: bool,
+}
+
+/// The values an expression can take, where a missing bound is infinite.
+/// Bounds are only ever too loose, never too tight, so that a claim made
+/// about every value in an interval holds for the expression.
+#[derive(Clone, Debug, Default)]
+pub struct Interval {
+	lo: Option<Bound>,
+	hi: Option<Bound>,
+}
+
+impl Interval {
+	fn point(value: BigRational) -> Self {
+		let bound = Some(Bound { value, strict: false });
+		Self { lo: bound.clone(), hi: bound }
+	}
+
+	fn between(lo: i32, hi: i32) -> Self {
+		let bound = |v: i32| Some(Bound { value: BigRational::from_integer(v.into()), strict: false });
+		Self { lo: bound(lo), hi: bound(hi) }
+	}
+
+	fn at_least(value: BigRational, strict: bool) -> Self {
+		Self { lo: Some(Bound { value, strict }), hi: None }
+	}
+
+	fn as_point(&self) -> Option<&BigRational> {
+		match (&self.lo, &self.hi) {
+			(Some(lo), Some(hi)) if lo.value == hi.value => Some(&lo.value),
+			_ => None,
+		}
+	}
+
+	fn is_nonnegative(&self) -> bool {
+		self.lo.as_ref().is_some_and(|lo| !lo.value.is_negative())
+	}
+
+	fn is_nonpositive(&self) -> bool {
+		self.hi.as_ref().is_some_and(|hi| !hi.value.is_positive())
+	}
+
+	fn neg(&self) -> Self {
+		let neg = |b: &Option<Bound>| b.as_ref().map(|b| Bound { value: -&b.value, strict: b.strict });
+		Self { lo: neg(&self.hi), hi: neg(&self.lo) }
+	}
+
+	fn add(&self, rhs: &Interval) -> Self {
+		let add = |a: &Option<Bound>, b: &Option<Bound>| match (a, b) {
+			(Some(a), Some(b)) => Some(Bound { value: &a.value + &b.value, strict: a.strict || b.strict }),
+			_ => None,
+		};
+		Self { lo: add(&self.lo, &rhs.lo), hi: add(&self.hi, &rhs.hi) }
+	}
+
+	fn scale(&self, factor: &BigRational) -> Self {
+		if factor.is_zero() { return Self::point(BigRational::zero()); }
+
+		let scaled = if factor.is_negative() { self.neg() } else { self.clone() };
+		let scale = |b: Option<Bound>| b.map(|b| Bound { value: b.value * factor.abs(), strict: b.strict });
+		Self { lo: scale(scaled.lo), hi: scale(scaled.hi) }
+	}
+
+	fn mul(&self, rhs: &Interval) -> Self {
+		if let Some(c) = self.as_point() { return rhs.scale(c); }
+		if let Some(c) = rhs.as_point() { return self.scale(c); }
+
+		match (self.is_nonnegative(), rhs.is_nonnegative(), self.is_nonpositive(), rhs.is_nonpositive()) {
+			(true, true, _, _) => {
+				let (a, b) = (self.lo.as_ref().unwrap(), rhs.lo.as_ref().unwrap());
+				// a product of two values above a bound, one of them positive, is above their product
+				let strict = (a.strict && (b.strict || b.value.is_positive())) || (b.strict && a.value.is_positive());
+				let hi = self.hi.as_ref().zip(rhs.hi.as_ref())
+					.map(|(a, b)| Bound { value: &a.value * &b.value, strict: false });
+				Self { lo: Some(Bound { value: &a.value * &b.value, strict }), hi }
+			},
+			(_, _, true, true) => self.neg().mul(&rhs.neg()),
+			(true, _, _, true) => self.mul(&rhs.neg()).neg(),
+			(_, true, true, _) => self.neg().mul(rhs).neg(),
+			_ => match (&self.lo, &self.hi, &rhs.lo, &rhs.hi) {
+				(Some(a), Some(b), Some(c), Some(d)) => {
+					let products = [&a.value * &c.value, &a.value * &d.value, &b.value * &c.value, &b.value * &d.value];
+					let lo = products.iter().min().unwrap().clone();
+					let hi = products.iter().max().unwrap().clone();
+					Self { lo: Some(Bound { value: lo, strict: false }), hi: Some(Bound { value: hi, strict: false }) }
+				},
+				_ => Self::default(),
+			},
+		}
+	}
+
+	fn pow(&self, exp: u32) -> Self {
+		if exp == 0 { return Self::point(BigRational::one()); }
+
+		let pow = |b: &Bound| Bound { value: num_traits::pow(b.value.clone(), exp as usize), strict: b.strict };
+		if exp % 2 == 1 || self.is_nonnegative() {
+			return Self { lo: self.lo.as_ref().map(pow), hi: self.hi.as_ref().map(pow) };
+		}
+		if self.is_nonpositive() {
+			return self.neg().pow(exp);
+		}
+
+		// an even power of values around zero
+		let hi = self.lo.as_ref().zip(self.hi.as_ref())
+			.map(|(lo, hi)| pow(&Bound { value: lo.value.abs().max(hi.value.abs()), strict: false }));
+		Self { lo: Some(Bound { value: BigRational::zero(), strict: false }), hi }
+	}
+
+	/// Tightens the bounds of an expression that only takes integer values.
+	fn round(&self) -> Self {
+		let round = |b: &Bound, up: bool| {
+			let v = match (up, b.value.is_integer(), b.strict) {
+				(true, true, true) => &b.value + BigRational::one(),
+				(false, true, true) => &b.value - BigRational::one(),
+				(true, _, _) => b.value.ceil(),
+				(false, _, _) => b.value.floor(),
+			};
+			Bound { value: v, strict: false }
+		};
+		Self { lo: self.lo.as_ref().map(|b| round(b, true)), hi: self.hi.as_ref().map(|b| round(b, false)) }
+	}
+}
+
+fn describe(bound: &Bound, lower: bool) -> String {
+	match (lower, bound.strict) {
+		(true, false) => format!("at least {}", bound.value),
+		(true, true) => format!("greater than {}", bound.value),
+		(false, false) => format!("at most {}", bound.value),
+		(false, true) => format!("less than {}", bound.value),
+	}
+}
+
+/// Bounds the values of an expression by those of its parts:
+/// even powers, square roots and absolute values aren't negative,
+/// and sums and products are bounded by the bounds of their operands.
+pub fn bound(expr: &ExprNode) -> Interval {
+	match &expr.item {
+		ExprItem::Literal(l) => match Number::from_literal(l) {
+			Number::Exact(v) => Interval::point(v),
+			Number::Approx(_) => Interval::default(),
+		},
+		ExprItem::Grouping(e) => bound(e),
+		ExprItem::Unary(e) => bound(e).neg(),
+		ExprItem::Term { lhs, rhs } => match expr.token.kind {
+			Plus => bound(lhs).add(&bound(rhs)),
+			_ => bound(lhs).add(&bound(rhs).neg()),
+		},
+		ExprItem::Factor { lhs, rhs, .. } => match expr.token.kind {
+			Multiply if lhs == rhs => bound(lhs).pow(2),
+			Multiply => bound(lhs).mul(&bound(rhs)),
+			_ => match bound(rhs).as_point() {
+				Some(c) if !c.is_zero() => bound(lhs).scale(&c.recip()),
+				_ => Interval::default(),
+			},
+		},
+		ExprItem::Power { base, power } => match bound(power).as_point().filter(|p| p.is_integer()).and_then(|p| p.to_integer().to_u32()) {
+			Some(exp) => bound(base).pow(exp),
+			None => Interval::default(),
+		},
+		ExprItem::Builtin { builtin, .. } => match builtin.name {
+			"sqrt" | "abs" => Interval::at_least(BigRational::zero(), false),
+			"exp" => Interval::at_least(BigRational::zero(), true),
+			"sin" | "cos" => Interval::between(-1, 1),
+			_ => Interval::default(),
+		},
+		_ => Interval::default(),
+	}
+}
+
+/// Decides `expr <kind> 0` from the bounds of `expr`. `is_integer` tells
+/// whether `expr` only takes integer values, which tightens its bounds.
+/// Returns whether it holds, along with why.
+pub fn compare(kind: &TokenKind, expr: &ExprNode, is_integer: bool) -> Option<(bool, String)> {
+	let loose = bound(expr);
+	let interval = if is_integer { loose.round() } else { loose.clone() };
+
+	let zero = BigRational::zero();
+	let above = |b: &Bound| b.value > zero || (b.value == zero && b.strict);
+	let at_or_above = |b: &Bound| b.value >= zero;
+	let below = |b: &Bound| b.value < zero || (b.value == zero && b.strict);
+	let at_or_below = |b: &Bound| b.value <= zero;
+
+	let (lo, hi) = (interval.lo.as_ref(), interval.hi.as_ref());
+	let (holds, lower) = match kind {
+		Greater if lo.is_some_and(above) => (true, true),
+		Greater if hi.is_some_and(at_or_below) => (false, false),
+		GreaterEqual if lo.is_some_and(at_or_above) => (true, true),
+		GreaterEqual if hi.is_some_and(below) => (false, false),
+		Lesser if hi.is_some_and(below) => (true, false),
+		Lesser if lo.is_some_and(at_or_above) => (false, true),
+		LesserEqual if hi.is_some_and(at_or_below) => (true, false),
+		LesserEqual if lo.is_some_and(above) => (false, true),
+		_ => return None,
+	};
+
+	let bound = if lower { interval.lo.as_ref()? } else { interval.hi.as_ref()? };
+	let original = if lower { loose.lo.as_ref()? } else { loose.hi.as_ref()? };
+	let reason = match original.value == bound.value && original.strict == bound.strict {
+		true => format!("lhs is {}!", describe(bound, lower)),
+		false => format!("lhs is {}, so as an integer {}!", describe(original, lower), describe(bound, lower)),
+	};
+	Some((holds, reason))
+}
+
+/// Writes a polynomial as a sum of multiples of squares and what's left,
+/// as in `(x + 2)^2 + 1`, or returns `None` if it has no squares.
+pub fn complete_square(poly: &Polynomial) -> Option<ExprNode> {
+	let (squares, rest) = poly.squares()?;
+	if squares.is_empty() { return None; }
+
+	let mut terms = Vec::new();
+	for (a, base) in squares {
+		let square = node(Power, ExprItem::Power {
+			base: Box::new(base.to_expr()?),
+			power: Box::new(Number::integer(2).to_expr()?),
+		});
+		let term = if a.abs().is_one() { square } else { implicit(Number::Exact(a.abs()).to_expr()?, square) };
+		terms.push((a.is_negative(), term));
+	}
+	match rest.as_constant() {
+		Some(c) if c.is_zero() => {},
+		Some(c) => terms.push((c.is_negative(), Number::Exact(c.abs()).to_expr()?)),
+		None => terms.push((false, rest.to_expr()?)),
+	}
+
+	let mut terms = terms.into_iter();
+	let (negative, first) = terms.next()?;
+	let mut sum = if negative { node(Minus, ExprItem::Unary(Box::new(first))) } else { first };
+	for (negative, term) in terms {
+		sum = node(if negative { Minus } else { Plus }, ExprItem::Term { lhs: Box::new(sum), rhs: Box::new(term) });
+	}
+	Some(regroup(&sum))
+}
+
+/// Whether an expression is a literal zero.
+pub fn is_zero(expr: &ExprNode) -> bool {
+	match &expr.item {
+		ExprItem::Grouping(e) => is_zero(e),
+		ExprItem::Literal(Literal::Integer(v)) => v.is_zero(),
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{parse::astprinter::ExprPrinter, testing::parse};
+
+	/// Decides `expr <kind> 0`.
+	fn compared(kind: TokenKind, expr: &str, is_integer: bool) -> Option<(bool, String)> {
+		compare(&kind, parse(&format!("$x: real\n$y: real\n$e := {}", expr)).variable("e"), is_integer)
+	}
+
+	#[test]
+	fn squares_are_not_negative() {
+		assert_eq!(compared(GreaterEqual, "(x + 2)^2", false), Some((true, "lhs is at least 0!".to_string())));
+		assert_eq!(compared(Greater, "x^2 + 1", false), Some((true, "lhs is at least 1!".to_string())));
+		assert_eq!(compared(Lesser, "x^2 + y^2 + 1", false).map(|c| c.0), Some(false));
+		assert_eq!(compared(Greater, "x^2", false), None);
+	}
+
+	#[test]
+	fn builtins_are_bounded() {
+		assert_eq!(compared(LesserEqual, "sin x - 1", false).map(|c| c.0), Some(true));
+		assert_eq!(compared(Greater, "sqrt x + abs y + 1", false).map(|c| c.0), Some(true));
+		assert_eq!(compared(Greater, "exp x", false).map(|c| c.0), Some(true));
+	}
+
+	#[test]
+	fn integers_have_tighter_bounds() {
+		assert_eq!(compared(Greater, "x^2 - 1 / 2", false), None);
+		assert_eq!(
+			compared(GreaterEqual, "x^2 + 1 / 2", true),
+			Some((true, "lhs is at least 1/2, so as an integer at least 1!".to_string())),
+		);
+	}
+
+	#[test]
+	fn completes_squares() {
+		let script = parse("$x: real\n$e := x^2 + 4 x + 5");
+		let poly = Polynomial::from_expr(script.variable("e")).unwrap();
+		assert_eq!(complete_square(&poly).map(|e| ExprPrinter::print(&e)), Some("(x + 2)^2 + 1".to_string()));
+	}
+}
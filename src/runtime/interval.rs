@@ -0,0 +1,393 @@
+use super::{
+	eval::{Number, evaluate},
+	polynomial::Polynomial,
+	rewrite::{children, with_children},
+};
+use crate::{
+	parse::ast::{ExprNode, ExprItem, Literal},
+	lex::token::TokenKind::*,
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use num_integer::Integer;
+use std::cmp::Ordering;
+
+/// How many units in the last place the platform's math functions
+/// (other than `sqrt`, which is rounded correctly) may be off by.
+const MATH_ULPS: u32 = 4;
+
+/// A closed interval of exact rationals that a value is known to lie in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interval {
+	pub lo: BigRational,
+	pub hi: BigRational,
+}
+
+/// The largest float that isn't larger than a rational.
+fn down(v: &BigRational) -> Option<f64> {
+	let mut f = v.to_f64().filter(|f| f.is_finite())?;
+	while BigRational::from_float(f)? > *v { f = f.next_down(); }
+	Some(f)
+}
+
+/// The smallest float that isn't smaller than a rational.
+fn up(v: &BigRational) -> Option<f64> {
+	let mut f = v.to_f64().filter(|f| f.is_finite())?;
+	while BigRational::from_float(f)? < *v { f = f.next_up(); }
+	Some(f)
+}
+
+/// The exact value of a decimal literal as it's written, such as `0.1`,
+/// which no float is.
+fn decimal(text: &str) -> Option<BigRational> {
+	let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+	let digits = BigInt::parse_bytes(format!("0{}{}", whole, fraction).as_bytes(), 10)?;
+	Some(BigRational::new(digits, BigInt::from(10).pow(fraction.len().try_into().ok()?)))
+}
+
+/// The exact value of a literal. Decimals are read from their source, as
+/// long as the source is where they came from.
+fn exact_literal(expr: &ExprNode) -> Option<BigRational> {
+	match &expr.item {
+		ExprItem::Literal(Literal::Integer(v)) => Some(BigRational::from_integer(v.clone())),
+		ExprItem::Literal(Literal::Float(v)) => {
+			let text = expr.token.span.get_part().ok().filter(|_| expr.token.kind == Float)?;
+			decimal(text).filter(|d| d.to_f64() == Some(*v))
+		},
+		_ => None,
+	}
+}
+
+/// Replaces the decimals of an expression by the fractions they're
+/// written as, where their source is known.
+fn exact_decimals(expr: &ExprNode) -> ExprNode {
+	if let ExprItem::Literal(Literal::Float(_)) = &expr.item {
+		if let Some(expr) = exact_literal(expr).and_then(|v| Number::Exact(v).to_expr()) {
+			return expr;
+		}
+	}
+	with_children(expr, children(expr).into_iter().map(exact_decimals).collect())
+}
+
+/// The difference of two expressions if it's the same for any values of
+/// their unknowns, such as that of `x` and `x + 0.1`.
+pub fn constant_difference(lhs: &ExprNode, rhs: &ExprNode) -> Option<BigRational> {
+	let (a, b) = (Polynomial::from_expr(&exact_decimals(lhs))?, Polynomial::from_expr(&exact_decimals(rhs))?);
+	a.sub(&b).as_constant()
+}
+
+impl Interval {
+	pub fn point(v: BigRational) -> Self {
+		Self { lo: v.clone(), hi: v }
+	}
+
+	/// The interval of a computed float that's at most `ulps`
+	/// units in the last place away from the actual value.
+	fn around(v: f64, ulps: u32) -> Option<Self> {
+		if !v.is_finite() { return None; }
+		let (mut lo, mut hi) = (v, v);
+		for _ in 0..ulps {
+			(lo, hi) = (lo.next_down(), hi.next_up());
+		}
+		Some(Self { lo: BigRational::from_float(lo)?, hi: BigRational::from_float(hi)? })
+	}
+
+	pub fn is_point(&self) -> bool {
+		self.lo == self.hi
+	}
+
+	pub fn midpoint(&self) -> BigRational {
+		(&self.lo + &self.hi) / BigRational::from_integer(2.into())
+	}
+
+	fn contains_zero(&self) -> bool {
+		!self.lo.is_positive() && !self.hi.is_negative()
+	}
+
+	/// The interval of the distances of the values from zero.
+	pub fn abs(&self) -> Self {
+		match (self.lo.is_negative(), self.hi.is_negative()) {
+			(false, _) => self.clone(),
+			(true, true) => Self { lo: -&self.hi, hi: -&self.lo },
+			(true, false) => Self { lo: BigRational::zero(), hi: (-&self.lo).max(self.hi.clone()) },
+		}
+	}
+
+	/// How all the values compare to zero, if they all do the same way.
+	pub fn sign(&self) -> Option<Ordering> {
+		if self.lo.is_positive() {
+			Some(Ordering::Greater)
+		} else if self.hi.is_negative() {
+			Some(Ordering::Less)
+		} else if self.lo.is_zero() && self.hi.is_zero() {
+			Some(Ordering::Equal)
+		} else {
+			None
+		}
+	}
+
+	/// Whether all values are within `tolerance` of zero, or none of
+	/// them are. Returns `None` if only some of them are.
+	pub fn within(&self, tolerance: &BigRational) -> Option<bool> {
+		let distance = self.abs();
+		if distance.hi <= *tolerance {
+			Some(true)
+		} else if distance.lo > *tolerance {
+			Some(false)
+		} else {
+			None
+		}
+	}
+}
+
+// arithmetic
+impl Interval {
+	fn neg(&self) -> Self {
+		Self { lo: -&self.hi, hi: -&self.lo }
+	}
+
+	fn add(&self, rhs: &Self) -> Self {
+		Self { lo: &self.lo + &rhs.lo, hi: &self.hi + &rhs.hi }
+	}
+
+	pub fn sub(&self, rhs: &Self) -> Self {
+		self.add(&rhs.neg())
+	}
+
+	fn mul(&self, rhs: &Self) -> Self {
+		let products = [&self.lo * &rhs.lo, &self.lo * &rhs.hi, &self.hi * &rhs.lo, &self.hi * &rhs.hi];
+		let lo = products.iter().min().cloned().unwrap_or_default();
+		let hi = products.iter().max().cloned().unwrap_or_default();
+		Self { lo, hi }
+	}
+
+	fn div(&self, rhs: &Self) -> Option<Self> {
+		if rhs.contains_zero() { return None; }
+		Some(self.mul(&Self { lo: rhs.hi.recip(), hi: rhs.lo.recip() }))
+	}
+
+	fn pow(&self, rhs: &Self) -> Option<Self> {
+		if !rhs.is_point() || !rhs.lo.is_integer() {
+			// x^y = exp(y ln x) for positive x
+			return self.ln()?.mul(rhs).exp();
+		}
+		let exp = &rhs.lo;
+		let exact = |v: &BigRational| match Number::Exact(v.clone()).checked_pow(&Number::Exact(exp.clone()))? {
+			Number::Exact(v) => Some(v),
+			Number::Approx(_) => None,
+		};
+		if exp.is_negative() {
+			return Self::point(BigRational::one()).div(&self.pow(&Self::point(-exp))?);
+		}
+
+		let (lo, hi) = (exact(&self.lo)?, exact(&self.hi)?);
+		Some(match exp.to_integer().is_even() {
+			true if self.contains_zero() => Self { lo: BigRational::zero(), hi: lo.max(hi) },
+			true if self.hi.is_negative() => Self { lo: hi, hi: lo },
+			_ => Self { lo, hi },
+		})
+	}
+}
+
+// functions
+impl Interval {
+	/// Applies a function that never decreases, computed to within `ulps`.
+	fn increasing(&self, f: fn(f64) -> f64, ulps: u32) -> Option<Self> {
+		let lo = Self::around(f(down(&self.lo)?), ulps)?.lo;
+		let hi = Self::around(f(up(&self.hi)?), ulps)?.hi;
+		Some(Self { lo, hi })
+	}
+
+	/// Applies a function that never changes faster than its argument,
+	/// such as `sin`, around the midpoint of the interval.
+	fn lipschitz(&self, f: fn(f64) -> f64) -> Option<Self> {
+		let m = self.midpoint();
+		let x = m.to_f64()?;
+		let spread = (&m - BigRational::from_float(x)?).abs() + (&self.hi - &m);
+		let y = Self::around(f(x), MATH_ULPS)?;
+
+		let one = BigRational::one();
+		Some(Self { lo: (y.lo - &spread).max(-&one), hi: (y.hi + spread).min(one) })
+	}
+
+	fn sqrt(&self) -> Option<Self> {
+		if self.lo.is_negative() { return None; }
+		// squares of rationals have exact roots
+		let root = BigRational::new(self.lo.numer().sqrt(), self.lo.denom().sqrt());
+		if self.is_point() && &root * &root == self.lo {
+			return Some(Self::point(root));
+		}
+		self.increasing(f64::sqrt, 1)
+	}
+
+	fn ln(&self) -> Option<Self> {
+		if !self.lo.is_positive() { return None; }
+		self.increasing(f64::ln, MATH_ULPS)
+	}
+
+	fn exp(&self) -> Option<Self> {
+		self.increasing(f64::exp, MATH_ULPS)
+	}
+
+	/// The value of a function at a point where it's known exactly, such as `ln 1`.
+	fn exact(name: &str, a: &Self) -> Option<BigRational> {
+		if !a.is_point() { return None; }
+		let (zero, one) = (BigRational::zero(), BigRational::one());
+		match name {
+			"sin" | "tan" if a.lo == zero => Some(zero),
+			"cos" | "exp" if a.lo == zero => Some(one),
+			"ln" | "log" if a.lo == one => Some(zero),
+			_ => None,
+		}
+	}
+
+	fn builtin(name: &str, args: &[Self]) -> Option<Self> {
+		if let Some(v) = args.first().and_then(|a| Self::exact(name, a)) {
+			return Some(Self::point(v));
+		}
+		let min = |a: &Self, b: &Self| Self { lo: a.lo.clone().min(b.lo.clone()), hi: a.hi.clone().min(b.hi.clone()) };
+		let max = |a: &Self, b: &Self| Self { lo: a.lo.clone().max(b.lo.clone()), hi: a.hi.clone().max(b.hi.clone()) };
+		let integer = |a: &Self| Some(a.lo.clone()).filter(|v| a.is_point() && v.is_integer()).map(|v| v.to_integer());
+
+		match (name, args) {
+			("sqrt", [a]) => a.sqrt(),
+			("abs", [a]) => Some(a.abs()),
+			("floor", [a]) => Some(Self { lo: a.lo.floor(), hi: a.hi.floor() }),
+			("ceil", [a]) => Some(Self { lo: a.lo.ceil(), hi: a.hi.ceil() }),
+			("sin", [a]) => a.lipschitz(f64::sin),
+			("cos", [a]) => a.lipschitz(f64::cos),
+			("tan", [a]) => a.lipschitz(f64::sin)?.div(&a.lipschitz(f64::cos)?),
+			("ln", [a]) => a.ln(),
+			("log", [a]) => {
+				if !a.lo.is_positive() { return None; }
+				a.increasing(f64::log10, MATH_ULPS)
+			},
+			("exp", [a]) => a.exp(),
+			("min", [a, b]) => Some(min(a, b)),
+			("max", [a, b]) => Some(max(a, b)),
+			("gcd" | "lcm", [a, b]) => {
+				let (a, b) = (integer(a)?, integer(b)?);
+				let v = if name == "gcd" { a.gcd(&b) } else { a.lcm(&b) };
+				Some(Self::point(BigRational::from_integer(v)))
+			},
+			_ => None,
+		}
+	}
+
+	/// Evaluates a closed expression to an interval its value lies in,
+	/// rounding outwards wherever it can't be computed exactly. Returns
+	/// `None` if the expression can't be evaluated (yet).
+	pub fn evaluate(expr: &ExprNode) -> Option<Self> {
+		match &expr.item {
+			ExprItem::Literal(Literal::Float(v)) => match exact_literal(expr) {
+				Some(v) => Some(Self::point(v)),
+				// a decimal lies halfway between its float's neighbours at worst
+				None => Self::around(*v, 1),
+			},
+			ExprItem::Literal(_) => exact_literal(expr).map(Self::point),
+			ExprItem::Grouping(e) => Self::evaluate(e),
+			ExprItem::Unary(e) => Some(Self::evaluate(e)?.neg()),
+			ExprItem::Term { lhs, rhs } => {
+				let (a, b) = (Self::evaluate(lhs)?, Self::evaluate(rhs)?);
+				Some(if expr.token.kind == Plus { a.add(&b) } else { a.sub(&b) })
+			},
+			ExprItem::Factor { lhs, rhs, .. } => {
+				let (a, b) = (Self::evaluate(lhs)?, Self::evaluate(rhs)?);
+				if expr.token.kind == Multiply { Some(a.mul(&b)) } else { a.div(&b) }
+			},
+			ExprItem::Power { base, power } => Self::evaluate(base)?.pow(&Self::evaluate(power)?),
+			ExprItem::Builtin { builtin, args, .. } => {
+				let args = args.iter().map(Self::evaluate).collect::<Option<Vec<Self>>>()?;
+				Self::builtin(builtin.name, &args)
+			},
+			ExprItem::Equality { .. } |
+			ExprItem::Variable { .. } |
+			ExprItem::Call { .. } |
+			ExprItem::Symbol(_) |
+			ExprItem::Generic(_) => None,
+		}
+	}
+}
+
+/// Whether two expressions are within `tolerance` of each other, if it can
+/// be told: symbolically if their difference is the same for any values of
+/// their unknowns, and by evaluating them otherwise.
+pub fn approximately_equal(lhs: &ExprNode, rhs: &ExprNode, tolerance: &BigRational) -> Option<bool> {
+	match constant_difference(lhs, rhs) {
+		Some(d) => Some(d.abs() <= *tolerance),
+		None => Interval::evaluate(lhs)?.sub(&Interval::evaluate(rhs)?).within(tolerance),
+	}
+}
+
+/// How the values of two closed expressions compare, if it can be told
+/// despite rounding: exactly if both of them can be computed exactly, and
+/// by the intervals they lie in otherwise.
+pub fn compare(lhs: &ExprNode, rhs: &ExprNode) -> Option<Ordering> {
+	match (evaluate(lhs)?, evaluate(rhs)?) {
+		(Number::Exact(a), Number::Exact(b)) => Some(a.cmp(&b)),
+		_ => Interval::evaluate(lhs)?.sub(&Interval::evaluate(rhs)?).sign(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::parse;
+
+	fn ratio(numer: i64, denom: i64) -> BigRational {
+		BigRational::new(numer.into(), denom.into())
+	}
+
+	fn evaluated(expr: &str) -> Option<Interval> {
+		Interval::evaluate(parse(&format!("$e := {}", expr)).variable("e"))
+	}
+
+	/// Whether `$a` and `$b` are within `tolerance` of each other.
+	fn close(a: &str, b: &str, tolerance: BigRational) -> Option<bool> {
+		let script = parse(&format!("$x: real\n$a := {}\n$b := {}", a, b));
+		approximately_equal(script.variable("a"), script.variable("b"), &tolerance)
+	}
+
+	#[test]
+	fn decimals_are_read_exactly() {
+		assert_eq!(decimal("0.1"), Some(ratio(1, 10)));
+		assert_eq!(decimal("12.50"), Some(ratio(25, 2)));
+		assert_eq!(evaluated("0.1 + 0.2"), Some(Interval::point(ratio(3, 10))));
+	}
+
+	#[test]
+	fn rounding_is_outward() {
+		let third = ratio(1, 3);
+		let (lo, hi) = (down(&third).unwrap(), up(&third).unwrap());
+		assert!(BigRational::from_float(lo).unwrap() < third && third < BigRational::from_float(hi).unwrap());
+
+		let root = evaluated("sqrt 2").unwrap();
+		assert!(!root.is_point());
+		assert!(&root.lo * &root.lo <= ratio(2, 1) && ratio(2, 1) <= &root.hi * &root.hi);
+	}
+
+	#[test]
+	fn bounds_contain_the_value() {
+		// sin(3.14159265358979) is about 3.2e-15
+		let sin = evaluated("sin 3.14159265358979").unwrap();
+		assert!(sin.lo.is_positive() && sin.hi < ratio(1, 1_000_000_000));
+		assert!(evaluated("1 / 0").is_none());
+	}
+
+	#[test]
+	fn within_is_undecided_across_the_tolerance() {
+		let interval = Interval { lo: ratio(-1, 10), hi: ratio(1, 10) };
+		assert_eq!(interval.within(&ratio(1, 5)), Some(true));
+		assert_eq!(interval.within(&ratio(1, 20)), None);
+		assert_eq!(Interval::point(ratio(1, 2)).within(&ratio(1, 5)), Some(false));
+	}
+
+	#[test]
+	fn constant_differences_are_exact() {
+		assert_eq!(close("x", "x + 0.0001", ratio(1, 1000)), Some(true));
+		assert_eq!(close("x", "x + 0.1", ratio(1, 1000)), Some(false));
+		// which floats would get wrong
+		assert_eq!(close("0.1 + 0.2", "0.3", ratio(0, 1)), Some(true));
+	}
+}
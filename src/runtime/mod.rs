@@ -1,2 +1,18 @@
 pub mod types;
-pub mod question;
\ No newline at end of file
+pub mod question;
+pub mod eval;
+pub mod builtins;
+pub mod rewrite;
+pub mod pattern;
+pub mod rules;
+pub mod simplify;
+pub mod polynomial;
+pub mod egraph;
+pub mod soundness;
+pub mod divisibility;
+pub mod existence;
+pub mod interval;
+pub mod inequality;
+pub mod cases;
+pub mod cost;
+pub mod solver;
\ No newline at end of file
@@ -0,0 +1,409 @@
+use super::{rewrite::{children, with_children, theory_children, regroup}, polynomial::Polynomial, types::Type};
+use crate::{
+	parse::{ast::{ExprNode, ExprItem, TheoryNode, TheoryItem, Parameter}, astprinter::ExprPrinter},
+	lex::token::{Token, TokenKind::{self, *}},
+};
+use std::{collections::HashMap, mem::discriminant};
+
+macro_rules! b {
+	($what:expr) => (Box::new($what));
+}
+
+/// The expressions bound to the metavariables of a pattern.
+pub type Bindings = HashMap<String, ExprNode>;
+
+fn strip_grouping(expr: &ExprNode) -> &ExprNode {
+	match &expr.item {
+		ExprItem::Grouping(e) => strip_grouping(e),
+		_ => expr,
+	}
+}
+
+/// The metavariables of a pattern, along with whether an unknown only
+/// takes integer values, which is what `int` metavariables are bound to.
+#[derive(Clone, Copy)]
+pub struct Metavariables<'a> {
+	pub parameters: &'a [Parameter],
+	pub is_integer: &'a dyn Fn(&str) -> bool,
+}
+
+impl Metavariables<'_> {
+	fn find(&self, name: &str) -> Option<&Parameter> {
+		self.parameters.iter().find(|p| p.name == name)
+	}
+
+	/// Whether a metavariable can be bound to an expression: one of type
+	/// `int` only to expressions that are integers for any values.
+	fn allows(&self, param: &Parameter, expr: &ExprNode) -> bool {
+		match param.annotation {
+			Some(Type::Int) => Polynomial::from_expr(expr).is_some_and(|p| p.is_integral(self.is_integer)),
+			_ => true,
+		}
+	}
+}
+
+fn generic_key(index: usize) -> String {
+	format!("..{}", index)
+}
+
+fn bind(bindings: &mut Bindings, key: String, expr: &ExprNode) -> bool {
+	match bindings.get(&key) {
+		Some(bound) => same_operands(bound, expr),
+		None => { bindings.insert(key, expr.clone()); true },
+	}
+}
+
+/// The most operands a sum or product may have to be matched up to
+/// permutation, larger ones are only matched the way they're written.
+const MAX_AC_OPERANDS: usize = 8;
+
+/// An operand of a flattened sum or product, along with whether it's
+/// subtracted (sums) or joined by juxtaposition (products).
+type Operand<'a> = (bool, &'a ExprNode);
+
+fn node(kind: TokenKind, item: ExprItem) -> ExprNode {
+	ExprNode { token: Token::synthetic(kind), item }
+}
+
+/// Whether the expression is a sum (`true`) or a product (`false`),
+/// the operations whose operands can be reordered.
+fn operator(expr: &ExprNode) -> Option<bool> {
+	match &expr.item {
+		ExprItem::Term { .. } => Some(true),
+		ExprItem::Factor { .. } if expr.token.kind == Multiply => Some(false),
+		_ => None,
+	}
+}
+
+/// Whether `pattern` is a sum or product that's matched associatively
+/// and commutatively against `expr`, which is the same operation.
+fn is_ac(pattern: &ExprNode, expr: &ExprNode) -> bool {
+	operator(pattern).is_some() && operator(pattern) == operator(expr)
+}
+
+fn flatten<'a>(expr: &'a ExprNode, sum: bool, flag: bool, out: &mut Vec<Operand<'a>>) {
+	let expr = strip_grouping(expr);
+	match &expr.item {
+		ExprItem::Term { lhs, rhs } if sum => {
+			flatten(lhs, sum, flag, out);
+			flatten(rhs, sum, flag != (expr.token.kind == Minus), out);
+		},
+		ExprItem::Factor { lhs, rhs, implicit } if !sum && expr.token.kind == Multiply => {
+			flatten(lhs, sum, flag, out);
+			flatten(rhs, sum, *implicit, out);
+		},
+		_ => out.push((flag, expr)),
+	}
+}
+
+/// Joins operands back into a sum or product.
+fn join(sum: bool, operands: &[Operand]) -> ExprNode {
+	let (first_flag, first) = operands[0];
+	let first = if sum && first_flag { node(Minus, ExprItem::Unary(b!(first.clone()))) } else { first.clone() };
+
+	operands[1..].iter().fold(first, |acc, (flag, e)| {
+		let (lhs, rhs) = (b!(acc), b!((*e).clone()));
+		match sum {
+			true => node(if *flag { Minus } else { Plus }, ExprItem::Term { lhs, rhs }),
+			false => node(Multiply, ExprItem::Factor { lhs, rhs, implicit: *flag }),
+		}
+	})
+}
+
+/// Returns the expression with the operands of all sums and products
+/// sorted, so expressions that only differ in the order of their
+/// operands become identical.
+pub fn sort_operands(expr: &ExprNode) -> ExprNode {
+	regroup(&sorted(expr))
+}
+
+fn sorted(expr: &ExprNode) -> ExprNode {
+	let expr = strip_grouping(expr);
+	let Some(sum) = operator(expr) else {
+		let children = children(expr).into_iter().map(sorted).collect();
+		return with_children(expr, children);
+	};
+
+	let mut operands = Vec::new();
+	flatten(expr, sum, false, &mut operands);
+	let mut operands: Vec<(bool, ExprNode)> = operands.into_iter()
+		.map(|(flag, e)| (sum && flag, sorted(e)))
+		.collect();
+	operands.sort_by_cached_key(|(flag, e)| (ExprPrinter::print(&regroup(e)), *flag));
+
+	let operands: Vec<Operand> = operands.iter().map(|(flag, e)| (*flag, e)).collect();
+	join(sum, &operands)
+}
+
+/// Whether two expressions are equal up to the order of the operands
+/// of their sums and products.
+pub fn same_operands(a: &ExprNode, b: &ExprNode) -> bool {
+	a == b || sort_operands(a) == sort_operands(b)
+}
+
+fn is_metavariable(pattern: &ExprNode, metavariables: Metavariables) -> bool {
+	match &strip_grouping(pattern).item {
+		ExprItem::Generic(_) => true,
+		ExprItem::Symbol(name) => metavariables.find(name).is_some(),
+		_ => false,
+	}
+}
+
+/// Matches the operands of a sum or product up to permutation. The last
+/// (added) metavariable absorbs all operands the others didn't match,
+/// such that `..0 - 9` matches `x - 9 + y` with `x + y` as `..0`.
+fn solutions_ac(pattern: &ExprNode, expr: &ExprNode, metavariables: Metavariables, bindings: Bindings) -> Vec<Bindings> {
+	let sum = operator(pattern) == Some(true);
+	let (mut patterns, mut operands) = (Vec::new(), Vec::new());
+	flatten(pattern, sum, false, &mut patterns);
+	flatten(expr, sum, false, &mut operands);
+
+	// the signs of products don't matter
+	if !sum {
+		patterns.iter_mut().for_each(|(flag, _)| *flag = false);
+	}
+
+	let rest = patterns.iter()
+		.rposition(|(flag, p)| !flag && is_metavariable(p, metavariables))
+		.map(|i| patterns.remove(i).1);
+	if operands.len() > MAX_AC_OPERANDS
+	|| operands.len() < patterns.len() + rest.is_some() as usize
+	|| (rest.is_none() && operands.len() != patterns.len()) {
+		return vec![];
+	}
+
+	let mut out = Vec::new();
+	assign(&patterns, &operands, &mut vec![false; operands.len()], sum, rest, metavariables, bindings, &mut out);
+	out
+}
+
+/// Assigns the patterns to distinct operands one by one, backtracking over
+/// every choice, and hands the unassigned operands to `rest` at the end.
+#[allow(clippy::too_many_arguments)]
+fn assign(patterns: &[Operand], operands: &[Operand], used: &mut Vec<bool>, sum: bool, rest: Option<&ExprNode>,
+	metavariables: Metavariables, bindings: Bindings, out: &mut Vec<Bindings>) {
+	let Some(((sign, pattern), patterns)) = patterns.split_first() else {
+		let left: Vec<Operand> = operands.iter().zip(used.iter())
+			.filter(|(_, used)| !**used)
+			.map(|(o, _)| *o)
+			.collect();
+		match rest {
+			Some(rest) => out.extend(solutions(rest, &regroup(&join(sum, &left)), metavariables, bindings)),
+			None => out.push(bindings),
+		}
+		return;
+	};
+
+	for (i, (flag, operand)) in operands.iter().enumerate() {
+		if used[i] || (sum && flag != sign) { continue; }
+
+		used[i] = true;
+		for b in solutions(pattern, operand, metavariables, bindings.clone()) {
+			assign(patterns, operands, used, sum, rest, metavariables, b, out);
+		}
+		used[i] = false;
+	}
+}
+
+/// Returns every way `expr` matches `pattern`, extending the given bindings.
+fn solutions(pattern: &ExprNode, expr: &ExprNode, metavariables: Metavariables, mut bindings: Bindings) -> Vec<Bindings> {
+	let (pattern, expr) = (strip_grouping(pattern), strip_grouping(expr));
+
+	match &pattern.item {
+		ExprItem::Generic(None) => return vec![bindings],
+		ExprItem::Generic(Some(i)) => return match bind(&mut bindings, generic_key(*i), expr) {
+			true => vec![bindings],
+			false => vec![],
+		},
+		ExprItem::Symbol(name) => if let Some(param) = metavariables.find(name) {
+			return match metavariables.allows(param, expr) && bind(&mut bindings, name.clone(), expr) {
+				true => vec![bindings],
+				false => vec![],
+			};
+		},
+		_ => {},
+	}
+
+	if is_ac(pattern, expr) {
+		return solutions_ac(pattern, expr, metavariables, bindings);
+	}
+	if discriminant(&pattern.item) != discriminant(&expr.item) { return vec![]; }
+
+	match &pattern.item {
+		ExprItem::Equality { .. } |
+		ExprItem::Term { .. } |
+		ExprItem::Factor { .. } => if pattern.token.kind != expr.token.kind { return vec![]; },
+		ExprItem::Call { path, args, .. } => match &expr.item {
+			ExprItem::Call { path: p, args: a, .. } if p == path && a.len() == args.len() => {},
+			_ => return vec![],
+		},
+		ExprItem::Builtin { builtin, .. } => match &expr.item {
+			ExprItem::Builtin { builtin: b, .. } if b.name == builtin.name => {},
+			_ => return vec![],
+		},
+		ExprItem::Unary(_) |
+		ExprItem::Power { .. } => {},
+		_ => return if pattern == expr { vec![bindings] } else { vec![] },
+	}
+
+	children(pattern).into_iter().zip(children(expr))
+		.fold(vec![bindings], |all, (p, e)| all.into_iter()
+			.flat_map(|b| solutions(p, e, metavariables, b))
+			.collect())
+}
+
+/// Returns every way `expr` matches `pattern`. Generics and symbols in the
+/// pattern that name one of the metavariables match any expression (of the
+/// metavariable's type), as long
+/// as every occurrence of the same (numbered) generic or metavariable matches
+/// the same expression. Anonymous generics (`..`) match independently. Sums
+/// and products are matched regardless of how their operands are ordered.
+pub fn match_all(pattern: &ExprNode, expr: &ExprNode, metavariables: Metavariables) -> Vec<Bindings> {
+	solutions(pattern, expr, metavariables, Bindings::new())
+}
+
+/// Matches `expr` against `pattern` like `match_all`,
+/// extending `bindings` with the first match there is.
+pub fn match_pattern(pattern: &ExprNode, expr: &ExprNode, metavariables: Metavariables, bindings: &mut Bindings) -> bool {
+	match solutions(pattern, expr, metavariables, bindings.clone()).into_iter().next() {
+		Some(b) => { *bindings = b; true },
+		None => false,
+	}
+}
+
+fn strip_theory_grouping(th: &TheoryNode) -> &TheoryNode {
+	match &th.item {
+		TheoryItem::Grouping(t) => strip_theory_grouping(t),
+		_ => th,
+	}
+}
+
+fn theory_solutions(pattern: &TheoryNode, th: &TheoryNode, metavariables: Metavariables, bindings: Bindings) -> Vec<Bindings> {
+	let (pattern, th) = (strip_theory_grouping(pattern), strip_theory_grouping(th));
+
+	match (&pattern.item, &th.item) {
+		(TheoryItem::Expression(p), TheoryItem::Expression(e)) => solutions(p, e, metavariables, bindings),
+		(p, t) if discriminant(p) == discriminant(t) && pattern.token.kind == th.token.kind =>
+			theory_children(pattern).into_iter().zip(theory_children(th))
+				.fold(vec![bindings], |all, (p, t)| all.into_iter()
+					.flat_map(|b| theory_solutions(p, t, metavariables, b))
+					.collect()),
+		_ => vec![],
+	}
+}
+
+/// Matches the theory `th` against the theory `pattern`, with the
+/// expressions in them matched the same way as by `match_pattern`.
+pub fn match_theory(pattern: &TheoryNode, th: &TheoryNode, metavariables: Metavariables, bindings: &mut Bindings) -> bool {
+	match theory_solutions(pattern, th, metavariables, bindings.clone()).into_iter().next() {
+		Some(b) => { *bindings = b; true },
+		None => false,
+	}
+}
+
+/// Replaces every bound metavariable in `template` by its binding.
+pub fn instantiate(template: &ExprNode, bindings: &Bindings) -> ExprNode {
+	let bound = match &template.item {
+		ExprItem::Symbol(name) => bindings.get(name),
+		ExprItem::Generic(Some(i)) => bindings.get(&generic_key(*i)),
+		_ => None,
+	};
+	if let Some(bound) = bound {
+		return bound.clone();
+	}
+
+	let children = children(template).into_iter()
+		.map(|c| instantiate(c, bindings))
+		.collect();
+	with_children(template, children)
+}
+
+/// Returns `true` if the expression still contains generics,
+/// which happens when a template uses generics its pattern doesn't bind.
+pub fn contains_generics(expr: &ExprNode) -> bool {
+	matches!(expr.item, ExprItem::Generic(_)) || children(expr).into_iter().any(contains_generics)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::{parse, Script};
+
+	fn no_integers(_: &str) -> bool { false }
+
+	/// Every way the variable `$e` matches the left side of the theorem `!t`.
+	fn matches(script: &Script) -> Vec<Bindings> {
+		let theorem = script.theorem("t");
+		let metavariables = Metavariables { parameters: &theorem.parameters, is_integer: &no_integers };
+		match_all(&theorem.lhs, script.variable("e"), metavariables)
+	}
+
+	#[test]
+	fn numbered_generic_binds_a_subexpression() {
+		let script = parse("$x: real\n!t := ..0^2 := 0\n$e := (x + 1)^2\n$b := x + 1");
+		let all = matches(&script);
+		assert_eq!(all.len(), 1);
+		assert_eq!(&all[0]["..0"], script.variable("b"));
+	}
+
+	#[test]
+	fn repeated_generic_matches_consistently() {
+		assert_eq!(matches(&parse("$x: real\n!t := ..0 - ..0 := 0\n$e := x^2 - x^2")).len(), 1);
+		assert!(matches(&parse("$x: real\n!t := ..0 - ..0 := 0\n$e := x^2 - x")).is_empty());
+	}
+
+	#[test]
+	fn anonymous_generics_match_independently() {
+		let script = parse("$x: real\n!t := .. - .. := 0\n$e := x^2 - x");
+		let all = matches(&script);
+		assert_eq!(all.len(), 1);
+		assert!(all[0].is_empty());
+	}
+
+	#[test]
+	fn instantiate_replaces_generics_by_their_bindings() {
+		let script = parse("$x: real\n!t := ..0 / ..1 := ..1 / ..0\n$e := (x + 1) / 2\n$b := 2 / (x + 1)");
+		let all = matches(&script);
+		assert_eq!(&instantiate(&script.theorem("t").rhs, &all[0]), script.variable("b"));
+		assert!(!contains_generics(&instantiate(&script.theorem("t").rhs, &all[0])));
+	}
+
+	#[test]
+	fn unbound_generics_are_left_in_place() {
+		let script = parse("$x: real\n!t := ..0 / 2 := ..1\n$e := x / 2");
+		assert!(contains_generics(&instantiate(&script.theorem("t").rhs, &matches(&script)[0])));
+	}
+
+	#[test]
+	fn int_metavariables_only_match_integers() {
+		let src = "$n: int\n$x: real\n!t(k: int) := k / 1 := k\n";
+		let is_n = |name: &str| name == "n";
+		for (e, integral) in [("2 n + 1", true), ("n / 3", false), ("x", false), ("x / 3", false)] {
+			let script = parse(&format!("{}$e := ({}) / 1", src, e));
+			let theorem = script.theorem("t");
+			let metavariables = Metavariables { parameters: &theorem.parameters, is_integer: &is_n };
+			assert_eq!(!match_all(&theorem.lhs, script.variable("e"), metavariables).is_empty(), integral, "{}", e);
+		}
+	}
+
+	#[test]
+	fn sums_match_up_to_permutation() {
+		let script = parse("$x: real\n$y: real\n!t := ..0 - 9 := ..0\n$e := x - 9 + y\n$b := x + y");
+		let all = matches(&script);
+		assert!(!all.is_empty());
+		assert!(same_operands(&all[0]["..0"], script.variable("b")));
+	}
+
+	#[test]
+	fn products_match_up_to_permutation() {
+		let script = parse("$x: real\n!t := ..0 * 2 := ..0\n$e := 2 x\n$b := x");
+		assert!(matches(&script).iter().any(|b| b["..0"] == *script.variable("b")));
+	}
+
+	#[test]
+	fn reordered_operands_are_the_same() {
+		let script = parse("$x: real\n$y: real\n$a := x + 2 y + 1\n$b := 1 + y 2 + x\n$c := x + y + 1");
+		assert!(same_operands(script.variable("a"), script.variable("b")));
+		assert!(!same_operands(script.variable("a"), script.variable("c")));
+	}
+}
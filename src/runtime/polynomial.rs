@@ -0,0 +1,440 @@
+use super::{eval::Number, rewrite::regroup};
+use crate::{
+	parse::{ast::*, astprinter::ExprPrinter},
+	lex::token::{Token, TokenKind::{self, *}},
+};
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::{cmp::Ordering, collections::BTreeMap};
+
+/// The highest power a polynomial is raised to before giving up.
+const MAX_DEGREE: u32 = 64;
+/// The largest number of terms a polynomial may have before giving up.
+const MAX_TERMS: usize = 1000;
+
+macro_rules! b {
+	($what:expr) => (Box::new($what));
+}
+
+/// A product of unknowns, each raised to a positive power. Anything that
+/// isn't a polynomial itself, such as `sqrt x` or a call, is treated as an
+/// unknown and identified by how it's printed.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Monomial(BTreeMap<String, u32>);
+
+impl Monomial {
+	pub fn degree(&self) -> u32 {
+		self.0.values().sum()
+	}
+
+	fn mul(&self, rhs: &Monomial) -> Monomial {
+		let mut out = self.clone();
+		for (name, exp) in &rhs.0 {
+			*out.0.entry(name.clone()).or_default() += exp;
+		}
+		out
+	}
+}
+
+impl Ord for Monomial {
+	//! graded lexicographic order: higher degrees first, then
+	//! higher powers of alphabetically earlier unknowns first
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.degree().cmp(&other.degree()).then_with(|| {
+			let (mut a, mut b) = (self.0.iter().peekable(), other.0.iter().peekable());
+			loop {
+				match (a.peek(), b.peek()) {
+					(None, None) => return Ordering::Equal,
+					(Some(_), None) => return Ordering::Greater,
+					(None, Some(_)) => return Ordering::Less,
+					(Some((x, i)), Some((y, j))) => match y.cmp(x).then(i.cmp(j)) {
+						Ordering::Equal => { a.next(); b.next(); },
+						ord => return ord,
+					},
+				}
+			}
+		})
+	}
+}
+
+impl PartialOrd for Monomial {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// A multivariate polynomial with rational coefficients,
+/// with like terms collected and without zero terms.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Polynomial {
+	terms: BTreeMap<Monomial, BigRational>,
+	/// the expressions of the unknowns, by name
+	unknowns: BTreeMap<String, ExprNode>,
+}
+
+// arithmetic
+impl Polynomial {
+	pub fn constant(c: BigRational) -> Self {
+		let mut p = Self::default();
+		if !c.is_zero() { p.terms.insert(Monomial::default(), c); }
+		p
+	}
+
+	fn unknown(expr: &ExprNode) -> Self {
+		let name = ExprPrinter::print(expr);
+		let mut p = Self::default();
+		p.terms.insert(Monomial(BTreeMap::from([(name.clone(), 1)])), BigRational::one());
+		p.unknowns.insert(name, expr.clone());
+		p
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.terms.is_empty()
+	}
+
+	/// The value of the polynomial if it doesn't contain any unknowns.
+	pub fn as_constant(&self) -> Option<BigRational> {
+		match self.terms.iter().next() {
+			None => Some(BigRational::zero()),
+			Some((m, c)) if self.terms.len() == 1 && m.degree() == 0 => Some(c.clone()),
+			_ => None,
+		}
+	}
+
+	/// Whether all unknowns of the polynomial are plain unknowns,
+	/// so that two different polynomials can't be equal everywhere.
+	pub fn is_free(&self) -> bool {
+		self.unknowns.values().all(|e| matches!(e.item, ExprItem::Symbol(_)))
+	}
+
+	/// The coefficient of the highest term.
+	pub fn leading_coefficient(&self) -> Option<&BigRational> {
+		self.terms.values().next_back()
+	}
+
+	/// Whether the polynomial only takes integer values, because its
+	/// coefficients are integers and its unknowns are plain unknowns
+	/// that `is_integer` says only take integer values.
+	pub fn is_integral(&self, is_integer: impl Fn(&str) -> bool) -> bool {
+		self.coefficients().all(|c| c.is_integer())
+			&& self.unknowns.iter().all(|(name, e)| matches!(e.item, ExprItem::Symbol(_)) && is_integer(name))
+	}
+
+	/// The names of the unknowns, along with their expressions.
+	pub fn unknowns(&self) -> &BTreeMap<String, ExprNode> {
+		&self.unknowns
+	}
+
+	/// The unknown of a polynomial in a single plain unknown,
+	/// along with its coefficients from the constant term up.
+	pub fn univariate(&self) -> Option<(String, Vec<BigRational>)> {
+		let mut names = self.terms.keys().flat_map(|m| m.0.keys());
+		let name = names.next()?;
+		if names.any(|n| n != name) || !matches!(self.unknowns.get(name)?.item, ExprItem::Symbol(_)) { return None; }
+
+		let degree = self.terms.keys().map(Monomial::degree).max()? as usize;
+		let mut coefficients = vec![BigRational::zero(); degree + 1];
+		for (m, c) in &self.terms {
+			coefficients[m.degree() as usize] = c.clone();
+		}
+		Some((name.clone(), coefficients))
+	}
+
+	/// Writes a polynomial of at most the second degree in plain unknowns
+	/// as multiples of squares of linear polynomials, along with what's
+	/// left, by completing the square for one unknown after another.
+	pub fn squares(&self) -> Option<(Vec<(BigRational, Polynomial)>, Polynomial)> {
+		if !self.is_free() || self.terms.keys().any(|m| m.degree() > 2) { return None; }
+
+		let mut rest = self.clone();
+		let mut squares = Vec::new();
+		for (name, expr) in &self.unknowns {
+			let Some(a) = rest.terms.get(&Monomial(BTreeMap::from([(name.clone(), 2)]))).cloned() else { continue };
+
+			// a x^2 + l x + r = a (x + l / 2a)^2 + r - a (l / 2a)^2
+			let empty = Polynomial { terms: BTreeMap::new(), unknowns: self.unknowns.clone() };
+			let (mut l, mut r) = (empty.clone(), empty);
+			for (m, c) in &rest.terms {
+				match m.0.get(name) {
+					Some(2) => {},
+					Some(_) => {
+						let mut m = m.clone();
+						m.0.remove(name);
+						l.add_term(m, c.clone());
+					},
+					None => r.add_term(m.clone(), c.clone()),
+				}
+			}
+
+			let shift = l.scale(&(&a + &a).recip());
+			rest = r.sub(&shift.mul(&shift)?.scale(&a));
+			squares.push((a, Self::unknown(expr).add(&shift)));
+		}
+		Some((squares, rest))
+	}
+
+	pub fn coefficients(&self) -> impl Iterator<Item = &BigRational> {
+		self.terms.values()
+	}
+
+	/// Applies `f` to every coefficient, dropping the terms that become zero.
+	pub fn map_coefficients(&self, f: impl Fn(&BigRational) -> BigRational) -> Polynomial {
+		let mut out = Polynomial { terms: BTreeMap::new(), unknowns: self.unknowns.clone() };
+		for (m, c) in &self.terms {
+			out.add_term(m.clone(), f(c));
+		}
+		out
+	}
+
+	/// The value of the polynomial for the given values of its unknowns.
+	pub fn evaluate(&self, values: &BTreeMap<String, BigRational>) -> Option<BigRational> {
+		self.terms.iter().try_fold(BigRational::zero(), |sum, (m, c)| {
+			let product = m.0.iter().try_fold(c.clone(), |p, (name, exp)| {
+				Some(p * num_traits::pow(values.get(name)?.clone(), *exp as usize))
+			})?;
+			Some(sum + product)
+		})
+	}
+
+	fn add_term(&mut self, monomial: Monomial, c: BigRational) {
+		let sum = self.terms.remove(&monomial).unwrap_or_default() + c;
+		if !sum.is_zero() { self.terms.insert(monomial, sum); }
+	}
+
+	fn merge_unknowns(&mut self, rhs: &Polynomial) {
+		for (name, expr) in &rhs.unknowns {
+			self.unknowns.entry(name.clone()).or_insert_with(|| expr.clone());
+		}
+	}
+
+	pub fn add(&self, rhs: &Polynomial) -> Polynomial {
+		let mut out = self.clone();
+		out.merge_unknowns(rhs);
+		for (m, c) in &rhs.terms {
+			out.add_term(m.clone(), c.clone());
+		}
+		out
+	}
+
+	pub fn scale(&self, factor: &BigRational) -> Polynomial {
+		if factor.is_zero() { return Self::default(); }
+		Polynomial {
+			terms: self.terms.iter().map(|(m, c)| (m.clone(), c * factor)).collect(),
+			unknowns: self.unknowns.clone(),
+		}
+	}
+
+	pub fn sub(&self, rhs: &Polynomial) -> Polynomial {
+		self.add(&rhs.scale(&-BigRational::one()))
+	}
+
+	pub fn mul(&self, rhs: &Polynomial) -> Option<Polynomial> {
+		let mut out = Polynomial { terms: BTreeMap::new(), unknowns: self.unknowns.clone() };
+		out.merge_unknowns(rhs);
+		for (m1, c1) in &self.terms {
+			for (m2, c2) in &rhs.terms {
+				out.add_term(m1.mul(m2), c1 * c2);
+			}
+		}
+		if out.terms.len() > MAX_TERMS { None } else { Some(out) }
+	}
+
+	pub fn pow(&self, exp: u32) -> Option<Polynomial> {
+		if exp > MAX_DEGREE { return None; }
+		(0..exp).try_fold(Self::constant(BigRational::one()), |acc, _| acc.mul(self))
+	}
+}
+
+// conversion
+impl Polynomial {
+	/// Converts an expression into a polynomial. Returns `None` if it isn't
+	/// one, such as when it divides by an unknown or contains a decimal.
+	pub fn from_expr(expr: &ExprNode) -> Option<Polynomial> {
+		match &expr.item {
+			ExprItem::Literal(Literal::Integer(v)) => Some(Self::constant(BigRational::from_integer(v.clone()))),
+			ExprItem::Literal(Literal::Float(_)) => None,
+			ExprItem::Grouping(e) => Self::from_expr(e),
+			ExprItem::Unary(e) => Some(Self::from_expr(e)?.scale(&-BigRational::one())),
+			ExprItem::Term { lhs, rhs } => {
+				let (a, b) = (Self::from_expr(lhs)?, Self::from_expr(rhs)?);
+				Some(if expr.token.kind == Plus { a.add(&b) } else { a.sub(&b) })
+			},
+			ExprItem::Factor { lhs, rhs, .. } => {
+				let (a, b) = (Self::from_expr(lhs)?, Self::from_expr(rhs)?);
+				if expr.token.kind == Multiply { return a.mul(&b); }
+
+				// only division by (non-zero) constants is polynomial
+				let divisor = b.as_constant().filter(|c| !c.is_zero())?;
+				Some(a.scale(&divisor.recip()))
+			},
+			ExprItem::Power { base, power } => {
+				let (a, b) = (Self::from_expr(base)?, Self::from_expr(power)?.as_constant()?);
+				if !b.is_integer() { return None; }
+
+				// constants may be raised to negative powers
+				match a.as_constant() {
+					Some(c) => Number::Exact(c).checked_pow(&Number::Exact(b)).and_then(|n| match n {
+						Number::Exact(v) => Some(Self::constant(v)),
+						Number::Approx(_) => None,
+					}),
+					None => a.pow(b.to_integer().to_u32()?),
+				}
+			},
+			ExprItem::Symbol(_) |
+			ExprItem::Variable { .. } |
+			ExprItem::Call { .. } |
+			ExprItem::Builtin { .. } => Some(Self::unknown(expr)),
+			ExprItem::Equality { .. } |
+			ExprItem::Generic(_) => None,
+		}
+	}
+
+	/// Prints the polynomial back as an expression, with the highest terms first.
+	pub fn to_expr(&self) -> Option<ExprNode> {
+		let mut terms = self.terms.iter().rev();
+		let Some((m, c)) = terms.next() else {
+			return Number::integer(0).to_expr();
+		};
+
+		let first = self.term_expr(m, &c.abs())?;
+		let mut sum = if c.is_negative() { node(Minus, ExprItem::Unary(b!(first))) } else { first };
+
+		for (m, c) in terms {
+			let kind = if c.is_negative() { Minus } else { Plus };
+			sum = node(kind, ExprItem::Term { lhs: b!(sum), rhs: b!(self.term_expr(m, &c.abs())?) });
+		}
+		Some(regroup(&sum))
+	}
+
+	fn term_expr(&self, monomial: &Monomial, c: &BigRational) -> Option<ExprNode> {
+		// a coefficient is written in front, as in `4 x`
+		let mut product = match (monomial.degree(), c.is_one()) {
+			(0, _) | (_, false) => Some(Number::Exact(c.clone()).to_expr()?),
+			_ => None,
+		};
+
+		for (name, exp) in &monomial.0 {
+			let unknown = self.unknowns.get(name)?.clone();
+			let factor = match exp {
+				1 => unknown,
+				_ => node(TokenKind::Power, ExprItem::Power {
+					base: b!(unknown),
+					power: b!(Number::integer(*exp).to_expr()?),
+				}),
+			};
+			product = Some(match product {
+				None => factor,
+				Some(p) => implicit(p, factor),
+			});
+		}
+		product
+	}
+}
+
+pub fn node(kind: TokenKind, item: ExprItem) -> ExprNode {
+	ExprNode { token: Token::synthetic(kind), item }
+}
+
+pub fn implicit(lhs: ExprNode, rhs: ExprNode) -> ExprNode {
+	node(Multiply, ExprItem::Factor { lhs: b!(lhs), rhs: b!(rhs), implicit: true })
+}
+
+fn strip_grouping(expr: &ExprNode) -> &ExprNode {
+	match &expr.item {
+		ExprItem::Grouping(e) => strip_grouping(e),
+		_ => expr,
+	}
+}
+
+/// Whether the expression is a polynomial in its canonical form.
+pub fn is_normalized(expr: &ExprNode) -> bool {
+	normalize(expr).is_some_and(|e| e == regroup(expr))
+}
+
+/// Returns the canonical form of a polynomial expression: expanded, with
+/// like terms collected and sorted. An equation `a = b` is normalized as
+/// `p = 0`, where `p` is `a - b` divided by its leading coefficient.
+pub fn normalize(expr: &ExprNode) -> Option<ExprNode> {
+	let expr = strip_grouping(expr);
+
+	let ExprItem::Equality { lhs, rhs } = &expr.item else {
+		return Polynomial::from_expr(expr)?.to_expr();
+	};
+
+	let diff = Polynomial::from_expr(lhs)?.sub(&Polynomial::from_expr(rhs)?);
+	let diff = match diff.leading_coefficient() {
+		Some(c) => diff.scale(&c.recip()),
+		None => diff,
+	};
+	Some(regroup(&ExprNode {
+		token: expr.token.clone(),
+		item: ExprItem::Equality { lhs: b!(diff.to_expr()?), rhs: b!(Number::integer(0).to_expr()?) },
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::parse;
+
+	fn normalized(expr: &str) -> Option<String> {
+		normalize(parse(&format!("$x: real\n$y: real\n$e := {}", expr)).variable("e"))
+			.map(|e| ExprPrinter::print(&e))
+	}
+
+	fn polynomial(expr: &str) -> Option<Polynomial> {
+		Polynomial::from_expr(parse(&format!("$x: real\n$y: real\n$e := {}", expr)).variable("e"))
+	}
+
+	fn ratio(numer: i64, denom: i64) -> BigRational {
+		BigRational::new(numer.into(), denom.into())
+	}
+
+	#[test]
+	fn expands_and_collects_like_terms() {
+		assert_eq!(normalized("(x + 1)^2 + y"), Some("x^2 + 2 x + y + 1".to_string()));
+		assert_eq!(normalized("(x + y) (x - y)"), Some("x^2 - y^2".to_string()));
+		assert_eq!(normalized("x / 2"), Some("1 / 2 x".to_string()));
+	}
+
+	#[test]
+	fn equal_polynomials_have_the_same_form() {
+		assert_eq!(polynomial("(x + 1)^2"), polynomial("1 + x (x + 1) + x"));
+		assert_ne!(polynomial("(x + 1)^2"), polynomial("x^2 + 1"));
+	}
+
+	#[test]
+	fn equations_are_normalized_as_monic_differences() {
+		assert_eq!(normalized("2 x = 4"), Some("x - 2 = 0".to_string()));
+	}
+
+	#[test]
+	fn only_polynomials_are_normalized() {
+		assert_eq!(normalized("x / y"), None);
+		assert_eq!(normalized("2^x"), None);
+	}
+
+	#[test]
+	fn univariate_coefficients() {
+		let (name, coefficients) = polynomial("x^2 - 1").unwrap().univariate().unwrap();
+		assert_eq!(name, "x");
+		assert_eq!(coefficients, [ratio(-1, 1), ratio(0, 1), ratio(1, 1)]);
+		assert!(polynomial("x y").unwrap().univariate().is_none());
+	}
+
+	#[test]
+	fn evaluates_at_a_point() {
+		let values = BTreeMap::from([("x".to_string(), ratio(1, 2)), ("y".to_string(), ratio(3, 1))]);
+		assert_eq!(polynomial("x^2 + y").unwrap().evaluate(&values), Some(ratio(13, 4)));
+	}
+
+	#[test]
+	fn completes_the_square() {
+		// x^2 + 4 x = (x + 2)^2 - 4
+		let (squares, rest) = polynomial("x^2 + 4 x").unwrap().squares().unwrap();
+		assert_eq!(squares.len(), 1);
+		assert_eq!(squares[0].0, ratio(1, 1));
+		assert_eq!(squares[0].1, polynomial("x + 2").unwrap());
+		assert_eq!(rest.as_constant(), Some(ratio(-4, 1)));
+	}
+}
@@ -1,8 +1,38 @@
-use crate::{parse::ast::TheoryNode, lex::token::Token};
+use crate::{parse::ast::{TheoryNode, Parameter}, lex::token::Token};
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct Question {
 	pub name: String,
 	pub token: Token,
+	/// universally quantified unknowns of the theory
+	pub parameters: Vec<Parameter>,
 	pub theory: TheoryNode,
-}
\ No newline at end of file
+	pub limits: Limits,
+	/// how far apart the sides of `~=` may be, if the question or its file says
+	pub tolerance: Option<f64>,
+}
+
+/// How far apart the sides of `~=` may be, unless told otherwise.
+pub const TOLERANCE: f64 = 1e-9;
+
+/// How much work the solver may put into a question before giving up,
+/// a limit that isn't set falls back to the one of the file, and then
+/// to the one given on the command line.
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+	pub max_states: Option<usize>,
+	pub timeout: Option<Duration>,
+	pub max_frontier: Option<usize>,
+}
+
+impl Limits {
+	/// Takes every limit that isn't set from `fallback`.
+	pub fn or(&self, fallback: &Limits) -> Limits {
+		Limits {
+			max_states: self.max_states.or(fallback.max_states),
+			timeout: self.timeout.or(fallback.timeout),
+			max_frontier: self.max_frontier.or(fallback.max_frontier),
+		}
+	}
+}
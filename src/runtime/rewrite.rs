@@ -0,0 +1,253 @@
+use crate::{
+	parse::ast::*,
+	lex::token::{Token, TokenKind},
+};
+
+/// A path of child indices leading from the root
+/// of a theory to one of its (sub-)expressions.
+pub type Position = Vec<usize>;
+
+/// A single rewrite of a sub-expression, as proposed by a rule.
+#[derive(Clone, Debug)]
+pub struct Rewrite {
+	pub description: String,
+	pub before: ExprNode,
+	pub after: ExprNode,
+	/// the side conditions that must hold for the rewrite to be valid
+	pub conditions: Vec<Condition>,
+}
+
+/// A side condition of a rewrite, along with why it holds once discharged.
+#[derive(Clone, Debug)]
+pub struct Condition {
+	pub theory: TheoryNode,
+	pub reason: Option<String>,
+}
+
+macro_rules! b {
+	($what:expr) => (Box::new($what));
+}
+
+// ============== children ===============
+
+pub fn theory_children(th: &TheoryNode) -> Vec<&TheoryNode> {
+	match &th.item {
+		TheoryItem::Logical { lhs, rhs } |
+		TheoryItem::Implies { lhs, rhs } |
+		TheoryItem::Comparison { lhs, rhs } => vec![lhs, rhs],
+		TheoryItem::Divisible { expr, divisor } => vec![expr, divisor],
+		TheoryItem::Unary(e) |
+		TheoryItem::Exists(e) |
+		TheoryItem::Grouping(e) => vec![e],
+		TheoryItem::Expression(_) => vec![],
+	}
+}
+
+fn with_theory_children(th: &TheoryNode, mut children: Vec<TheoryNode>) -> TheoryNode {
+	let mut next = || b!(children.remove(0));
+	let item = match &th.item {
+		TheoryItem::Logical { .. } => TheoryItem::Logical { lhs: next(), rhs: next() },
+		TheoryItem::Implies { .. } => TheoryItem::Implies { lhs: next(), rhs: next() },
+		TheoryItem::Comparison { .. } => TheoryItem::Comparison { lhs: next(), rhs: next() },
+		TheoryItem::Divisible { .. } => TheoryItem::Divisible { expr: next(), divisor: next() },
+		TheoryItem::Unary(_) => TheoryItem::Unary(next()),
+		TheoryItem::Exists(_) => TheoryItem::Exists(next()),
+		TheoryItem::Grouping(_) => TheoryItem::Grouping(next()),
+		TheoryItem::Expression(e) => TheoryItem::Expression(e.clone()),
+	};
+	TheoryNode { token: th.token.clone(), item }
+}
+
+pub fn children(expr: &ExprNode) -> Vec<&ExprNode> {
+	//! the definition of a variable is not considered a child
+	match &expr.item {
+		ExprItem::Equality { lhs, rhs } |
+		ExprItem::Term { lhs, rhs } |
+		ExprItem::Factor { lhs, rhs, .. } => vec![lhs, rhs],
+		ExprItem::Power { base, power } => vec![base, power],
+		ExprItem::Unary(e) |
+		ExprItem::Grouping(e) => vec![e],
+		ExprItem::Call { args, .. } |
+		ExprItem::Builtin { args, .. } => args.iter().collect(),
+		ExprItem::Variable { .. } |
+		ExprItem::Symbol(_) |
+		ExprItem::Generic(_) |
+		ExprItem::Literal(_) => vec![],
+	}
+}
+
+pub fn with_children(expr: &ExprNode, mut children: Vec<ExprNode>) -> ExprNode {
+	let mut next = || b!(children.remove(0));
+	let item = match &expr.item {
+		ExprItem::Equality { .. } => ExprItem::Equality { lhs: next(), rhs: next() },
+		ExprItem::Term { .. } => ExprItem::Term { lhs: next(), rhs: next() },
+		ExprItem::Factor { implicit, .. } => ExprItem::Factor { lhs: next(), rhs: next(), implicit: *implicit },
+		ExprItem::Power { .. } => ExprItem::Power { base: next(), power: next() },
+		ExprItem::Unary(_) => ExprItem::Unary(next()),
+		ExprItem::Grouping(_) => ExprItem::Grouping(next()),
+		ExprItem::Call { path, function, .. } => ExprItem::Call {
+			path: path.clone(),
+			args: std::mem::take(&mut children),
+			function: function.clone(),
+		},
+		ExprItem::Builtin { builtin, parens, .. } => ExprItem::Builtin {
+			builtin,
+			args: std::mem::take(&mut children),
+			parens: *parens,
+		},
+		item => item.clone(),
+	};
+	ExprNode { token: expr.token.clone(), item }
+}
+
+// ============== positions ==============
+
+fn collect_expr<'a>(expr: &'a ExprNode, pos: &mut Position, out: &mut Vec<(Position, &'a ExprNode)>) {
+	out.push((pos.clone(), expr));
+	for (i, child) in children(expr).into_iter().enumerate() {
+		pos.push(i);
+		collect_expr(child, pos, out);
+		pos.pop();
+	}
+}
+
+fn collect_theory<'a>(th: &'a TheoryNode, pos: &mut Position, out: &mut Vec<(Position, &'a ExprNode)>) {
+	if let TheoryItem::Expression(e) = &th.item {
+		return collect_expr(e, pos, out);
+	}
+	for (i, child) in theory_children(th).into_iter().enumerate() {
+		pos.push(i);
+		collect_theory(child, pos, out);
+		pos.pop();
+	}
+}
+
+/// Returns the outermost expressions of the theory.
+pub fn theory_expressions(th: &TheoryNode) -> Vec<&ExprNode> {
+	match &th.item {
+		TheoryItem::Expression(e) => vec![e],
+		_ => theory_children(th).into_iter().flat_map(theory_expressions).collect(),
+	}
+}
+
+/// Returns the outermost expressions of the theory along with their positions.
+pub fn outermost_expressions(th: &TheoryNode) -> Vec<(Position, &ExprNode)> {
+	let outermost = theory_expressions(th);
+	subexpressions(th).into_iter()
+		.filter(|(_, e)| outermost.iter().any(|o| std::ptr::eq(*o, *e)))
+		.collect()
+}
+
+/// Returns all (sub-)expressions of the theory along with their positions.
+pub fn subexpressions(th: &TheoryNode) -> Vec<(Position, &ExprNode)> {
+	let mut out = Vec::new();
+	collect_theory(th, &mut Vec::new(), &mut out);
+	out
+}
+
+fn replace_in_expr(expr: &ExprNode, pos: &[usize], new: &ExprNode) -> ExprNode {
+	if pos.is_empty() { return new.clone(); }
+
+	let children = children(expr).into_iter().enumerate()
+		.map(|(i, c)| if i == pos[0] { replace_in_expr(c, &pos[1..], new) } else { c.clone() })
+		.collect();
+	with_children(expr, children)
+}
+
+/// Returns a copy of the theory with the expression at `pos` replaced by `new`.
+pub fn replace_at(th: &TheoryNode, pos: &[usize], new: &ExprNode) -> TheoryNode {
+	if let TheoryItem::Expression(e) = &th.item {
+		return TheoryNode {
+			token: th.token.clone(),
+			item: TheoryItem::Expression(replace_in_expr(e, pos, new)),
+		};
+	}
+
+	let children = theory_children(th).into_iter().enumerate()
+		.map(|(i, c)| if i == pos[0] { replace_at(c, &pos[1..], new) } else { c.clone() })
+		.collect();
+	with_theory_children(th, children)
+}
+
+// =============== grouping ===============
+
+const ATOM_PRECEDENCE: u8 = 7;
+const POWER_PRECEDENCE: u8 = 6;
+const PREFIX_PRECEDENCE: u8 = 5;
+const UNARY_PRECEDENCE: u8 = 4;
+
+fn precedence(expr: &ExprNode) -> u8 {
+	match &expr.item {
+		ExprItem::Equality { .. } => 1,
+		ExprItem::Term { .. } => 2,
+		ExprItem::Factor { .. } => 3,
+		ExprItem::Unary(_) => UNARY_PRECEDENCE,
+		ExprItem::Power { .. } => POWER_PRECEDENCE,
+		// the argument of `sqrt x` extends to the right like that of a negation
+		ExprItem::Builtin { parens: false, .. } => PREFIX_PRECEDENCE,
+		ExprItem::Grouping(e) => precedence(e),
+		ExprItem::Variable { .. } |
+		ExprItem::Call { .. } |
+		ExprItem::Builtin { .. } |
+		ExprItem::Symbol(_) |
+		ExprItem::Generic(_) |
+		ExprItem::Literal(_) => ATOM_PRECEDENCE,
+	}
+}
+
+/// Returns the minimal precedence each child of `expr` must have to be
+/// printed without parentheses, mirroring the precedence of the parser.
+fn required_precedences(expr: &ExprNode) -> Vec<u8> {
+	match &expr.item {
+		ExprItem::Equality { .. } => vec![1, 2],
+		ExprItem::Term { .. } => vec![2, 3],
+		// `4 -x` would read as a subtraction
+		ExprItem::Factor { implicit: true, .. } => vec![3, PREFIX_PRECEDENCE],
+		ExprItem::Factor { .. } => vec![3, UNARY_PRECEDENCE],
+		ExprItem::Power { .. } => vec![ATOM_PRECEDENCE, UNARY_PRECEDENCE],
+		ExprItem::Unary(_) => vec![UNARY_PRECEDENCE],
+		ExprItem::Call { args, .. } |
+		ExprItem::Builtin { args, parens: true, .. } => vec![1; args.len()],
+		ExprItem::Builtin { args, parens: false, .. } => vec![UNARY_PRECEDENCE; args.len()],
+		_ => vec![],
+	}
+}
+
+fn strip_grouping(expr: &ExprNode) -> &ExprNode {
+	match &expr.item {
+		ExprItem::Grouping(e) => strip_grouping(e),
+		_ => expr,
+	}
+}
+
+fn group(expr: ExprNode) -> ExprNode {
+	ExprNode { token: Token::synthetic(TokenKind::LeftParen), item: ExprItem::Grouping(b!(expr)) }
+}
+
+/// Removes all groupings from the expression and re-inserts
+/// only those required by the precedence of its operators.
+pub fn regroup(expr: &ExprNode) -> ExprNode {
+	let expr = strip_grouping(expr);
+	let required = required_precedences(expr);
+
+	let children = children(expr).into_iter().zip(required)
+		.map(|(c, req)| {
+			let c = regroup(c);
+			if precedence(&c) < req { group(c) } else { c }
+		})
+		.collect();
+	with_children(expr, children)
+}
+
+/// Regroups every expression in the theory. Expressions used as
+/// atoms are grouped the same way the parser groups them.
+pub fn regroup_theory(th: &TheoryNode) -> TheoryNode {
+	if let TheoryItem::Expression(e) = &th.item {
+		let e = regroup(e);
+		let e = if precedence(&e) < UNARY_PRECEDENCE { group(e) } else { e };
+		return TheoryNode { token: th.token.clone(), item: TheoryItem::Expression(e) };
+	}
+
+	let children = theory_children(th).into_iter().map(regroup_theory).collect();
+	with_theory_children(th, children)
+}
@@ -0,0 +1,82 @@
+use super::{
+	rewrite::{Rewrite, Condition, regroup, regroup_theory, outermost_expressions, replace_at},
+	pattern::{Bindings, Metavariables, match_all, instantiate, contains_generics},
+};
+use crate::parse::ast::{ExprNode, ExprItem, Theorem, TheoryNode};
+
+/// A rule proposes rewrites of a single (sub-)expression.
+/// `is_integer` tells whether an unknown only takes integer values.
+pub trait Rule {
+	fn apply(&self, expr: &ExprNode, is_integer: &dyn Fn(&str) -> bool) -> Vec<Rewrite>;
+}
+
+fn instantiate_theory(th: &TheoryNode, bindings: &Bindings) -> TheoryNode {
+	let instantiated = outermost_expressions(th).into_iter()
+		.fold(th.clone(), |t, (pos, e)| replace_at(&t, &pos, &instantiate(e, bindings)));
+	regroup_theory(&instantiated)
+}
+
+/// Substitutes a variable by its definition, or
+/// a function call by the body of the function.
+pub struct Substitute;
+
+impl Rule for Substitute {
+	fn apply(&self, expr: &ExprNode, _: &dyn Fn(&str) -> bool) -> Vec<Rewrite> {
+		match &expr.item {
+			ExprItem::Variable { path, expr: definition } => vec![Rewrite {
+				description: format!("substitute `${}`", path),
+				before: expr.clone(),
+				after: regroup(definition),
+				conditions: vec![],
+			}],
+			ExprItem::Call { path, args, function } => {
+				let bindings: Bindings = function.parameters.iter()
+					.map(|p| p.name.clone())
+					.zip(args.iter().cloned())
+					.collect();
+
+				vec![Rewrite {
+					description: format!("substitute `@{}`", path),
+					before: expr.clone(),
+					after: regroup(&instantiate(&function.body, &bindings)),
+					conditions: vec![],
+				}]
+			},
+			_ => vec![],
+		}
+	}
+}
+
+/// Rewrites an expression matching the lhs of a theorem into its rhs.
+pub struct ApplyTheorem {
+	pub path: String,
+	pub theorem: Theorem,
+}
+
+impl Rule for ApplyTheorem {
+	fn apply(&self, expr: &ExprNode, is_integer: &dyn Fn(&str) -> bool) -> Vec<Rewrite> {
+		let mut rewrites: Vec<Rewrite> = Vec::new();
+		let metavariables = Metavariables { parameters: &self.theorem.parameters, is_integer };
+
+		for bindings in match_all(&self.theorem.lhs, expr, metavariables) {
+			let after = regroup(&instantiate(&self.theorem.rhs, &bindings));
+			if &after == expr || contains_generics(&after) { continue; }
+			if rewrites.iter().any(|r| r.after == after) { continue; }
+
+			// the guard has to be fully bound to be checked at all
+			let guard = self.theorem.guard.as_ref().map(|g| instantiate_theory(g, &bindings));
+			if guard.as_ref().is_some_and(|g| outermost_expressions(g).iter().any(|(_, e)| contains_generics(e))) {
+				continue;
+			}
+
+			rewrites.push(Rewrite {
+				description: format!("rewrite using `{}`", self.path),
+				before: expr.clone(),
+				after,
+				conditions: guard.into_iter().map(|theory| Condition { theory, reason: None }).collect(),
+			});
+		}
+
+		rewrites
+	}
+}
@@ -0,0 +1,345 @@
+use super::{
+	eval::Number,
+	rewrite::{Rewrite, regroup},
+	builtins::Builtin,
+};
+use crate::{
+	parse::ast::{self, *},
+	lex::token::{Token, TokenKind::{self, *}},
+};
+
+macro_rules! b {
+	($what:expr) => (Box::new($what));
+}
+
+/// Finds the simplifications of an expression that can be done in a
+/// single pass: folding constants, removing identities such as `x + 0`,
+/// `1 * x` or `x^1` and combining the literals of sums and products.
+/// Only the innermost simplifiable expressions are simplified, so a
+/// simplification never depends on another one of the same pass.
+pub struct Simplifier {
+	rewrites: Vec<Rewrite>,
+}
+
+impl Simplifier {
+	/// Returns the distinct simplifications of one pass over the
+	/// expressions, along with how often each of them was found.
+	pub fn pass<'a>(exprs: impl IntoIterator<Item = &'a ExprNode>) -> Vec<(Rewrite, usize)> {
+		let mut simplifier = Simplifier { rewrites: Vec::new() };
+		for expr in exprs {
+			simplifier.visit(expr);
+		}
+
+		let mut merged: Vec<(Rewrite, usize)> = Vec::new();
+		for rewrite in simplifier.rewrites {
+			match merged.iter_mut().find(|(r, _)| r.before == rewrite.before) {
+				Some((_, count)) => *count += 1,
+				None => merged.push((rewrite, 1)),
+			}
+		}
+		merged
+	}
+
+	fn simplify(&mut self, node: &ExprNode, children_simplified: bool) -> bool {
+		if children_simplified { return true; }
+
+		let simplified = fold(node)
+			.or_else(|| identity(node))
+			.or_else(|| combine_literals(node));
+
+		match simplified.map(|e| regroup(&e)) {
+			Some(after) if &after != node => {
+				self.rewrites.push(Rewrite {
+					description: String::from("simplify"),
+					before: node.clone(),
+					after,
+					conditions: vec![],
+				});
+				true
+			},
+			_ => false,
+		}
+	}
+
+	fn visit_all(&mut self, nodes: &[&ExprNode]) -> bool {
+		// visit every child, so all independent simplifications are found
+		nodes.iter().fold(false, |found, node| self.visit(node) | found)
+	}
+}
+
+impl ExprVisitor<bool> for Simplifier {
+	fn visit_equality(&mut self, _node: &ExprNode, lhs: &ExprNode, rhs: &ExprNode) -> bool {
+		//! equations are never simplified as a whole
+		self.visit_all(&[lhs, rhs])
+	}
+
+	fn visit_term(&mut self, node: &ExprNode, lhs: &ExprNode, rhs: &ExprNode) -> bool {
+		let found = self.visit_all(&[lhs, rhs]);
+		self.simplify(node, found)
+	}
+
+	fn visit_factor(&mut self, node: &ExprNode, lhs: &ExprNode, rhs: &ExprNode) -> bool {
+		let found = self.visit_all(&[lhs, rhs]);
+		self.simplify(node, found)
+	}
+
+	fn visit_unary(&mut self, node: &ExprNode, expr: &ExprNode) -> bool {
+		let found = self.visit(expr);
+		self.simplify(node, found)
+	}
+
+	fn visit_power(&mut self, node: &ExprNode, base: &ExprNode, power: &ExprNode) -> bool {
+		let found = self.visit_all(&[base, power]);
+		self.simplify(node, found)
+	}
+
+	fn visit_grouping(&mut self, _node: &ExprNode, expr: &ExprNode) -> bool {
+		//
+		self.visit(expr)
+	}
+
+	fn visit_variable(&mut self, _node: &ExprNode, _path: &str, _expr: &ExprNode) -> bool {
+		//
+		false
+	}
+
+	fn visit_call(&mut self, _node: &ExprNode, _path: &str, args: &[ExprNode], _function: &ast::Function) -> bool {
+		self.visit_all(&args.iter().collect::<Vec<&ExprNode>>())
+	}
+
+	fn visit_builtin(&mut self, node: &ExprNode, _builtin: &Builtin, args: &[ExprNode]) -> bool {
+		let found = self.visit_all(&args.iter().collect::<Vec<&ExprNode>>());
+		self.simplify(node, found)
+	}
+
+	fn visit_symbol(&mut self, _node: &ExprNode, _name: &str) -> bool {
+		//
+		false
+	}
+
+	fn visit_generic(&mut self, _node: &ExprNode, _index: Option<usize>) -> bool {
+		//
+		false
+	}
+
+	fn visit_literal(&mut self, _node: &ExprNode, _literal: &Literal) -> bool {
+		//
+		false
+	}
+}
+
+// ============ simplifications ============
+
+fn number(expr: &ExprNode) -> Option<Number> {
+	Number::from_expr(expr)
+}
+
+fn strip_grouping(expr: &ExprNode) -> &ExprNode {
+	match &expr.item {
+		ExprItem::Grouping(e) => strip_grouping(e),
+		_ => expr,
+	}
+}
+
+fn node(kind: TokenKind, item: ExprItem) -> ExprNode {
+	ExprNode { token: Token::synthetic(kind), item }
+}
+
+fn negate(expr: ExprNode) -> ExprNode {
+	node(Minus, ExprItem::Unary(b!(expr)))
+}
+
+/// Folds an operation on numbers into a single number.
+fn fold(expr: &ExprNode) -> Option<ExprNode> {
+	let (operands, result) = match &expr.item {
+		ExprItem::Term { lhs, rhs } => {
+			let (a, b) = (number(lhs)?, number(rhs)?);
+			let result = if expr.token.kind == Plus { a.checked_add(&b) } else { a.checked_sub(&b) };
+			(vec![a, b], result)
+		},
+		ExprItem::Factor { lhs, rhs, .. } => {
+			let (a, b) = (number(lhs)?, number(rhs)?);
+			let result = if expr.token.kind == Multiply { a.checked_mul(&b) } else { a.checked_div(&b) };
+			(vec![a, b], result)
+		},
+		ExprItem::Power { base, power } => {
+			let (a, b) = (number(base)?, number(power)?);
+			let result = a.checked_pow(&b);
+			(vec![a, b], result)
+		},
+		ExprItem::Builtin { builtin, args, .. } => {
+			let args = args.iter().map(number).collect::<Option<Vec<Number>>>()?;
+			let result = (builtin.eval)(&args);
+			(args, result)
+		},
+		ExprItem::Unary(e) => match &strip_grouping(e).item {
+			// a negated literal is already as simple as it gets
+			ExprItem::Literal(_) => return None,
+			_ => return number(e)?.checked_neg()?.to_expr(),
+		},
+		_ => return None,
+	};
+
+	// don't turn exact numbers into approximations
+	let result = result?;
+	if result.is_approx() && !operands.iter().any(Number::is_approx) {
+		None
+	} else {
+		result.to_expr()
+	}
+}
+
+/// Removes operations that don't change their operand.
+fn identity(expr: &ExprNode) -> Option<ExprNode> {
+	let is = |e: &ExprNode, v: i32| number(e).is_some_and(|n| n == Number::integer(v));
+
+	match &expr.item {
+		ExprItem::Term { lhs, rhs } if is(rhs, 0) => Some((**lhs).clone()),
+		ExprItem::Term { lhs, rhs } if is(lhs, 0) => Some(match expr.token.kind {
+			Plus => (**rhs).clone(),
+			_ => negate((**rhs).clone()),
+		}),
+		ExprItem::Factor { lhs, rhs, .. } if is(rhs, 1) => Some((**lhs).clone()),
+		ExprItem::Factor { lhs, rhs, .. } if is(lhs, 1) && expr.token.kind == Multiply => Some((**rhs).clone()),
+		ExprItem::Power { base, power } if is(power, 1) => Some((**base).clone()),
+		ExprItem::Unary(e) => match &strip_grouping(e).item {
+			ExprItem::Unary(e) => Some((**e).clone()),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+/// Flattens a sum into its operands, each with whether it's subtracted.
+fn summands(expr: &ExprNode, negative: bool, out: &mut Vec<(bool, ExprNode)>) {
+	let expr = strip_grouping(expr);
+	match &expr.item {
+		ExprItem::Term { lhs, rhs } => {
+			summands(lhs, negative, out);
+			summands(rhs, negative != (expr.token.kind == Minus), out);
+		},
+		_ => out.push((negative, expr.clone())),
+	}
+}
+
+/// Flattens a product into its factors, divisions are left as they are.
+fn factors(expr: &ExprNode, out: &mut Vec<ExprNode>) {
+	let expr = strip_grouping(expr);
+	match &expr.item {
+		ExprItem::Factor { lhs, rhs, .. } if expr.token.kind == Multiply => {
+			factors(lhs, out);
+			factors(rhs, out);
+		},
+		_ => out.push(expr.clone()),
+	}
+}
+
+/// Combines the number literals of a sum or product with other operands,
+/// such as `x + 2 - y + 3` into `x - y + 5` and `2 x * 3` into `6 x`.
+fn combine_literals(expr: &ExprNode) -> Option<ExprNode> {
+	match &expr.item {
+		ExprItem::Term { .. } => {
+			let mut operands = Vec::new();
+			summands(expr, false, &mut operands);
+			let (numbers, others): (Vec<_>, Vec<_>) = operands.into_iter()
+				.partition(|(_, e)| number(e).is_some());
+			if numbers.len() < 2 || others.is_empty() { return None; }
+
+			let mut total = Number::integer(0);
+			for (negative, e) in &numbers {
+				let n = number(e)?;
+				total = if *negative { total.checked_sub(&n)? } else { total.checked_add(&n)? };
+			}
+
+			let mut others = others.into_iter();
+			let (negative, first) = others.next()?;
+			let mut sum = if negative { negate(first) } else { first };
+			for (negative, e) in others {
+				sum = node(if negative { Minus } else { Plus }, ExprItem::Term { lhs: b!(sum), rhs: b!(e) });
+			}
+
+			if !total.is_zero() {
+				let kind = if total.is_negative() { Minus } else { Plus };
+				let total = if total.is_negative() { total.checked_neg()? } else { total };
+				sum = node(kind, ExprItem::Term { lhs: b!(sum), rhs: b!(total.to_expr()?) });
+			}
+			Some(sum)
+		},
+		ExprItem::Factor { implicit, .. } if expr.token.kind == Multiply => {
+			let mut operands = Vec::new();
+			factors(expr, &mut operands);
+			let (numbers, others): (Vec<_>, Vec<_>) = operands.into_iter()
+				.partition(|e| number(e).is_some());
+			if numbers.len() < 2 || others.is_empty() { return None; }
+
+			let mut total = Number::integer(1);
+			for e in &numbers {
+				total = total.checked_mul(&number(e)?)?;
+			}
+			if total.is_zero() { return total.to_expr(); }
+
+			// a coefficient is written in front, as in `6 x`
+			let mut others = others.into_iter();
+			let mut product = others.next()?;
+			if total != Number::integer(1) {
+				product = node(Multiply, ExprItem::Factor { lhs: b!(total.to_expr()?), rhs: b!(product), implicit: true });
+			}
+			for e in others {
+				product = node(Multiply, ExprItem::Factor { lhs: b!(product), rhs: b!(e), implicit: *implicit });
+			}
+			Some(product)
+		},
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{parse::astprinter::ExprPrinter, testing::parse};
+
+	/// The simplifications of one pass over `$e`, printed.
+	fn pass(expr: &str) -> Vec<(String, String, usize)> {
+		let script = parse(&format!("$x: real\n$e := {}", expr));
+		Simplifier::pass([script.variable("e")]).into_iter()
+			.map(|(r, n)| (ExprPrinter::print(&r.before), ExprPrinter::print(&r.after), n))
+			.collect()
+	}
+
+	fn rewrite(before: &str, after: &str, count: usize) -> (String, String, usize) {
+		(before.to_string(), after.to_string(), count)
+	}
+
+	#[test]
+	fn folds_constants() {
+		assert_eq!(pass("4 / 2 + 6 / 2"), [rewrite("4 / 2", "2", 1), rewrite("6 / 2", "3", 1)]);
+	}
+
+	#[test]
+	fn merges_identical_simplifications() {
+		assert_eq!(pass("4 / 2 + 4 / 2"), [rewrite("4 / 2", "2", 2)]);
+	}
+
+	#[test]
+	fn removes_identities() {
+		assert_eq!(pass("x + 0"), [rewrite("x + 0", "x", 1)]);
+		assert_eq!(pass("1 * x"), [rewrite("1 * x", "x", 1)]);
+		assert_eq!(pass("x^1"), [rewrite("x^1", "x", 1)]);
+	}
+
+	#[test]
+	fn combines_literals() {
+		assert_eq!(pass("2 + x + 3"), [rewrite("2 + x + 3", "x + 5", 1)]);
+	}
+
+	#[test]
+	fn simplifies_innermost_first() {
+		assert_eq!(pass("(2 + 3) * 4"), [rewrite("2 + 3", "5", 1)]);
+	}
+
+	#[test]
+	fn leaves_simple_expressions_alone() {
+		assert!(pass("x + 2").is_empty());
+		assert!(pass("2 x^2").is_empty());
+	}
+}
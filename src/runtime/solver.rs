@@ -0,0 +1,1284 @@
+use super::{
+	question::{Question, Limits, TOLERANCE},
+	eval::{Number, evaluate, evaluate_equation},
+	rewrite::{Position, Rewrite, subexpressions, with_children, theory_expressions, outermost_expressions, replace_at, regroup, regroup_theory},
+	rules::{Rule, Substitute, ApplyTheorem},
+	simplify::Simplifier,
+	polynomial::{Polynomial, normalize, is_normalized},
+	pattern::{Bindings, Metavariables, match_all, match_theory, instantiate, contains_generics, sort_operands, same_operands},
+	cost::{self, Cost, Combined},
+	egraph::EGraph,
+	soundness::{falsify, format_assignment, free_unknowns},
+	divisibility::Divisibility,
+	existence::{self, Solutions},
+	interval::{self, Interval, constant_difference},
+	inequality,
+	cases,
+	types::Type,
+};
+use crate::{
+	cli::{CliArgs, Strategy},
+	parse::{Context, ast::{self, *}, astprinter::{TheoryPrinter, ExprPrinter}},
+	object::question::{SQuestion, SStep, AnswerType},
+	lex::token::{Token, TokenKind::{self, *}},
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use std::{cell::{Cell, RefCell}, cmp::Reverse, collections::{BinaryHeap, HashMap, HashSet}, time::Instant};
+
+/// The maximum amount of states the solver will consider
+/// before giving up on a question, unless told otherwise.
+const MAX_STATES: usize = 100_000;
+/// The maximum amount of members of a class that are tried in place
+/// of a child when saturating, and of variants tried per node.
+const MAX_MEMBERS: usize = 4;
+const MAX_VARIANTS: usize = 16;
+/// How deeply side conditions are proven as sub-goals of sub-goals,
+/// and how many states proving a single one may consider.
+const MAX_GUARD_DEPTH: usize = 2;
+const MAX_GUARD_STATES: usize = 1_000;
+
+/// The truth value of a theory, along with the
+/// reason why the theory has that value.
+#[derive(Clone, Debug, Default)]
+pub struct Verdict {
+	pub is_true: bool,
+	pub conclusion: String,
+	/// the values an existence question holds for, if it's one
+	pub solutions: Option<Solutions>,
+	/// whether the theory is true or false for any values of its
+	/// unknowns, rather than just for some of them
+	pub everywhere: bool,
+}
+
+impl Verdict {
+	fn evaluated(is_true: bool) -> Self {
+		Self { is_true, conclusion: format!("the theory evaluates to {}!", is_true), everywhere: true, ..Default::default() }
+	}
+}
+
+/// The truth value of a combination of theories, as far as it's known.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Truth {
+	True,
+	/// doesn't hold for some values of the unknowns, or for any if `everywhere`
+	False { everywhere: bool },
+	/// holds for some values of the unknowns, which doesn't decide anything yet
+	Somewhere,
+	Unknown,
+}
+
+/// The proof of a combination of theories, built up operand by operand.
+#[derive(Default)]
+struct Combination {
+	steps: Vec<SStep>,
+	operands: usize,
+	steps_tried: u64,
+}
+
+/// How a search ended.
+enum Outcome {
+	Decided(Vec<SStep>, Verdict),
+	/// every reachable state was considered without deciding the theory
+	Exhausted,
+	/// a search limit was hit, with the reason why
+	GaveUp(String),
+}
+
+/// The limits of a single search, with the timeout turned into a deadline.
+struct Budget {
+	limits: Limits,
+	deadline: Option<Instant>,
+}
+
+impl Budget {
+	fn new(limits: Limits) -> Self {
+		let deadline = limits.timeout.and_then(|t| Instant::now().checked_add(t));
+		Self { limits, deadline }
+	}
+
+	/// Returns why the search has to stop, if it has to.
+	fn exceeded(&self, states: usize, frontier: usize) -> Option<String> {
+		if states >= self.limits.max_states.unwrap_or(MAX_STATES) {
+			Some(format!("gave up after considering {} states!", states))
+		} else if self.limits.max_frontier.is_some_and(|max| frontier > max) {
+			Some(format!("gave up with {} states left to expand!", frontier))
+		} else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+			Some(format!("gave up after {}s!", self.limits.timeout.unwrap_or_default().as_secs_f64()))
+		} else {
+			None
+		}
+	}
+}
+
+struct SearchNode {
+	state: TheoryNode,
+	parent: usize,
+	rewrite: Option<Rewrite>,
+}
+
+/// One side of a bidirectional search, searching
+/// through the states of a single side of the theory.
+struct Half {
+	nodes: Vec<SearchNode>,
+	seen: HashMap<String, usize>,
+	frontier: BinaryHeap<Reverse<(usize, usize)>>,
+	/// the first state of this side that can't get any simpler
+	settled: Option<usize>,
+}
+
+impl Half {
+	fn new(root: TheoryNode, cost: usize) -> Self {
+		let settled = Solver::is_settled(&root).then_some(0);
+		Self {
+			seen: HashMap::from([(Solver::side_key(&root), 0)]),
+			frontier: BinaryHeap::from([Reverse((cost, 0))]),
+			nodes: vec![SearchNode { state: root, parent: 0, rewrite: None }],
+			settled,
+		}
+	}
+}
+
+pub struct Solver {
+	rules: Vec<Box<dyn Rule>>,
+	conclusions: Vec<(String, ast::Conclusion)>,
+	cost: Box<dyn Cost>,
+	limits: Limits,
+	strategy: Strategy,
+	/// how many sub-goals deep the solver currently is
+	depth: Cell<usize>,
+	/// the side conditions tried so far, with why they hold if they do
+	discharged: RefCell<HashMap<String, Option<String>>>,
+	/// the types of the unknowns
+	unknowns: HashMap<String, Type>,
+	/// the parameters of the question being solved
+	parameters: RefCell<Vec<Parameter>>,
+	/// how far apart the sides of `~=` may be in questions that don't say
+	default_tolerance: f64,
+	/// how far apart the sides of `~=` may be in the question being solved
+	tolerance: Cell<f64>,
+	/// whether the side conditions of steps are written for markdown
+	markdown: bool,
+}
+
+// deciding
+impl Solver {
+	fn atom_expr(th: &TheoryNode) -> Option<&ExprNode> {
+		match &th.item {
+			TheoryItem::Expression(e) => Some(e),
+			TheoryItem::Grouping(t) => Self::atom_expr(t),
+			_ => None,
+		}
+	}
+
+	fn sides_match(lhs: &TheoryNode, rhs: &TheoryNode) -> bool {
+		match (Self::atom_expr(lhs), Self::atom_expr(rhs)) {
+			(Some(a), Some(b)) => same_operands(a, b),
+			_ => false,
+		}
+	}
+
+	/// Decides a definitional (in)equality of two different polynomials
+	/// in normal form, which differ somewhere if their unknowns are free.
+	fn distinct_polynomials(th: &TheoryNode, lhs: &TheoryNode, rhs: &TheoryNode) -> Option<Verdict> {
+		let (a, b) = (Self::atom_expr(lhs)?, Self::atom_expr(rhs)?);
+		if !is_normalized(a) || !is_normalized(b) { return None; }
+
+		let (p, q) = (Polynomial::from_expr(a)?, Polynomial::from_expr(b)?);
+		if p == q || !p.is_free() || !q.is_free() { return None; }
+
+		Some(Verdict {
+			is_true: th.token.kind == DefNotEquals,
+			conclusion: String::from("lhs and rhs are different polynomials!"),
+			..Default::default()
+		})
+	}
+
+	fn describe(conclusion: &ast::Conclusion) -> String {
+		let mut th = &conclusion.theory;
+		while let TheoryItem::Grouping(t) = &th.item { th = t; }
+
+		let truth = conclusion.truth;
+		match (&th.item, &th.token.kind) {
+			(TheoryItem::Divisible { .. }, _) if truth => String::from("lhs is divisible by rhs!"),
+			(TheoryItem::Divisible { .. }, _) => String::from("lhs is not divisible by rhs!"),
+			(TheoryItem::Implies { .. }, kind) if (*kind == Implies) == truth => String::from("lhs matches rhs!"),
+			(TheoryItem::Implies { .. }, _) => String::from("lhs doesn't match rhs!"),
+			(TheoryItem::Exists(_), _) if truth => String::from("lhs exists!"),
+			(TheoryItem::Exists(_), _) => String::from("lhs doesn't exist!"),
+			_ => format!("the theory is {}!", truth),
+		}
+	}
+
+	/// Returns the verdict of the first conclusion the theory matches.
+	fn conclude(&self, th: &TheoryNode) -> Option<Verdict> {
+		self.conclusions.iter()
+			.find(|(_, c)| {
+				let metavariables = Metavariables { parameters: &c.parameters, is_integer: &|name| self.is_integer(name) };
+				match_theory(&c.theory, th, metavariables, &mut Bindings::new())
+			})
+			.map(|(path, c)| Verdict {
+				is_true: c.truth,
+				conclusion: format!("{} ({})", Self::describe(c), path),
+				// true conclusions hold for any values, false ones may only fail for some
+				everywhere: c.truth,
+				..Default::default()
+			})
+	}
+
+	/// Returns the truth value of the theory in its current state,
+	/// or `None` if it can't be decided (yet).
+	fn decide(&self, th: &TheoryNode) -> Option<Verdict> {
+		if let Some(verdict) = self.conclude(th) {
+			return Some(verdict);
+		}
+
+		let matched = |is_true| Verdict { is_true, conclusion: String::from("lhs matches rhs!"), everywhere: true, ..Default::default() };
+
+		match &th.item {
+			TheoryItem::Grouping(t) => self.decide(t),
+			// the operands of `~`, `&` and `|` hold or don't at each single point,
+			// so only what they are for any values decides their combination
+			TheoryItem::Unary(t) => match self.decide(t)? {
+				v if v.everywhere => Some(Verdict::evaluated(!v.is_true)),
+				_ => None,
+			},
+			TheoryItem::Logical { lhs, rhs } => {
+				let (a, b) = (self.decide(lhs)?, self.decide(rhs)?);
+				let (always, never) = (|v: &Verdict| v.is_true && v.everywhere, |v: &Verdict| !v.is_true && v.everywhere);
+				match th.token.kind {
+					Or if always(&a) || always(&b) => Some(Verdict::evaluated(true)),
+					Or if never(&a) && never(&b) => Some(Verdict::evaluated(false)),
+					Or => None,
+					_ if always(&a) && always(&b) => Some(Verdict::evaluated(true)),
+					_ if never(&a) || never(&b) => Some(Verdict::evaluated(false)),
+					_ if !a.is_true || !b.is_true => Some(Verdict { everywhere: false, ..Verdict::evaluated(false) }),
+					_ => None,
+				}
+			},
+			TheoryItem::Implies { lhs, rhs } => {
+				let negate = th.token.kind == NotImplies;
+				if Self::sides_match(lhs, rhs) {
+					return Some(matched(!negate));
+				}
+				let (a, b) = (self.decide(lhs)?.is_true, self.decide(rhs)?.is_true);
+				Some(Verdict::evaluated((a == b) != negate))
+			},
+			TheoryItem::Comparison { lhs, rhs } => {
+				if Self::sides_match(lhs, rhs) {
+					return Some(matched(matches!(th.token.kind,
+						DefEquals | GreaterEqual | LesserEqual | RoughlyEquals)));
+				}
+				// different polynomials differ somewhere, but a side
+				// condition has to hold for the values at hand
+				if matches!(th.token.kind, DefEquals | DefNotEquals) {
+					if let Some(verdict) = Self::distinct_polynomials(th, lhs, rhs) {
+						if !verdict.is_true || self.depth.get() == 0 {
+							return Some(verdict);
+						}
+					}
+				}
+				let (lhs, rhs) = (Self::atom_expr(lhs)?, Self::atom_expr(rhs)?);
+				if th.token.kind == RoughlyEquals {
+					return self.approximate(lhs, rhs);
+				}
+				// rounded values only decide a comparison if the bounds of their errors do
+				let (Some(a), Some(b), Some(order)) = (evaluate(lhs), evaluate(rhs), interval::compare(lhs, rhs)) else {
+					return self.decide_order(&th.token.kind, lhs, rhs);
+				};
+				let is_true = match th.token.kind {
+					DefEquals => order.is_eq(),
+					DefNotEquals => order.is_ne(),
+					Greater => order.is_gt(),
+					GreaterEqual => order.is_ge(),
+					Lesser => order.is_lt(),
+					LesserEqual => order.is_le(),
+					_ => return None,
+				};
+				Some(Verdict { is_true, conclusion: format!("lhs evaluates to {}, rhs evaluates to {}!", a, b), everywhere: true, ..Default::default() })
+			},
+			TheoryItem::Divisible { expr, divisor } => {
+				let (expr, divisor) = (Self::atom_expr(expr)?, Self::atom_expr(divisor)?);
+				match (evaluate(expr), evaluate(divisor)) {
+					(Some(a), Some(b)) => Some(Verdict {
+						is_true: a.is_multiple_of(&b)?,
+						conclusion: format!("lhs evaluates to {}, rhs evaluates to {}!", a, b),
+						everywhere: true,
+						..Default::default()
+					}),
+					_ => self.split_residues(expr, divisor),
+				}
+			},
+			TheoryItem::Exists(t) => {
+				let existence = existence::solve(Self::atom_expr(t)?, |name| self.annotation(name))?;
+				Some(Verdict { is_true: existence.exists, conclusion: existence.conclusion, solutions: Some(existence.solutions), everywhere: true })
+			},
+			TheoryItem::Expression(e) => evaluate_equation(e).map(Verdict::evaluated),
+		}
+	}
+}
+
+// approximations
+impl Solver {
+	fn decimal(n: &Number) -> String {
+		match n {
+			Number::Exact(v) if !v.is_integer() => format!("{} (about {})", v, n.to_f64()),
+			_ => n.to_string(),
+		}
+	}
+
+	fn approximation(v: &Interval) -> String {
+		match v.is_point() {
+			true => Self::decimal(&Number::Exact(v.lo.clone())),
+			false => format!("about {}", Number::Exact(v.midpoint()).to_f64()),
+		}
+	}
+
+	/// Decides whether two expressions are within the tolerance of each
+	/// other. A difference that's the same for any values of the unknowns
+	/// is exact, otherwise both sides are evaluated to intervals, and the
+	/// verdict is only given if their rounding can't change it.
+	fn approximate(&self, lhs: &ExprNode, rhs: &ExprNode) -> Option<Verdict> {
+		let tolerance = self.tolerance.get();
+		let (a, b) = (Interval::evaluate(lhs), Interval::evaluate(rhs));
+		let difference = match (constant_difference(lhs, rhs), &a, &b) {
+			(Some(d), ..) => Interval::point(d),
+			(None, Some(a), Some(b)) => a.sub(b),
+			_ => return None,
+		};
+		let is_true = difference.within(&BigRational::from_float(tolerance)?)?;
+
+		let distance = Self::approximation(&difference.abs());
+		let values = match (a, b) {
+			(Some(a), Some(b)) => format!("lhs evaluates to {}, rhs evaluates to {}, which differ by {}",
+				Self::approximation(&a), Self::approximation(&b), distance),
+			_ => format!("lhs and rhs differ by {} for any values", distance),
+		};
+		Some(Verdict {
+			is_true,
+			conclusion: format!("{}, {} the tolerance of {}!", values, if is_true { "within" } else { "beyond" }, tolerance),
+			everywhere: true,
+			..Default::default()
+		})
+	}
+}
+
+// inequalities
+impl Solver {
+	fn is_order(kind: &TokenKind) -> bool {
+		matches!(kind, Greater | GreaterEqual | Lesser | LesserEqual)
+	}
+
+	/// Whether an expression only takes integer values, because it's a
+	/// polynomial with integer coefficients in integer unknowns.
+	fn is_integral(&self, expr: &ExprNode) -> bool {
+		Polynomial::from_expr(expr).is_some_and(|p| p.is_integral(|name| self.is_integer(name)))
+	}
+
+	/// Decides an inequality that has been brought into the form `lhs <> 0`
+	/// by bounding its lhs.
+	fn decide_order(&self, kind: &TokenKind, lhs: &ExprNode, rhs: &ExprNode) -> Option<Verdict> {
+		if !Self::is_order(kind) || !inequality::is_zero(rhs) { return None; }
+
+		let (is_true, conclusion) = inequality::compare(kind, lhs, self.is_integral(lhs))?;
+		Some(Verdict { is_true, conclusion, everywhere: true, ..Default::default() })
+	}
+
+	/// The transformations of an inequality: subtracting its rhs from
+	/// both sides, and completing the square of its lhs.
+	fn transform_order(&self, state: &TheoryNode) -> Vec<(Rewrite, TheoryNode)> {
+		let mut th = state;
+		while let TheoryItem::Grouping(t) = &th.item { th = t; }
+		let TheoryItem::Comparison { lhs, rhs } = &th.item else { return vec![] };
+		let Some((lhs, rhs)) = Self::atom_expr(lhs).zip(Self::atom_expr(rhs)) else { return vec![] };
+		if !Self::is_order(&th.token.kind) { return vec![] }
+
+		let transform = |description: &str, after: ExprNode| {
+			let Some(zero) = Number::integer(0).to_expr() else { return vec![] };
+			let next = Self::with_sides(th, &Self::atom(&after), &Self::atom(&zero));
+			vec![(Rewrite { description: String::from(description), before: lhs.clone(), after, conditions: vec![] }, next)]
+		};
+
+		if !inequality::is_zero(rhs) {
+			let difference = Polynomial::from_expr(lhs).zip(Polynomial::from_expr(rhs)).and_then(|(a, b)| a.sub(&b).to_expr());
+			let difference = difference.unwrap_or_else(|| regroup(&ExprNode {
+				token: Token::synthetic(Minus),
+				item: ExprItem::Term { lhs: Box::new(lhs.clone()), rhs: Box::new(rhs.clone()) },
+			}));
+			return transform("subtract rhs from both sides", difference);
+		}
+
+		match Polynomial::from_expr(lhs).and_then(|p| inequality::complete_square(&p)) {
+			Some(completed) if completed != regroup(lhs) => transform("complete the square", completed),
+			_ => vec![],
+		}
+	}
+}
+
+// divisibility
+impl Solver {
+	fn is_integer(&self, name: &str) -> bool {
+		self.annotation(name) == Some(Type::Int)
+	}
+
+	/// The type of an unknown, as a parameter of the question or declared.
+	fn annotation(&self, name: &str) -> Option<Type> {
+		match self.parameters.borrow().iter().find(|p| p.name == name) {
+			Some(p) => p.annotation,
+			None => self.unknowns.get(name).copied(),
+		}
+	}
+
+	fn divisibility(&self, expr: &ExprNode, divisor: &ExprNode) -> Option<Divisibility> {
+		Divisibility::new(expr, divisor, |name| self.is_integer(name))
+	}
+
+	/// Decides a divisibility that can't be reduced any further
+	/// by checking it for every residue of its unknowns.
+	fn split_residues(&self, expr: &ExprNode, divisor: &ExprNode) -> Option<Verdict> {
+		let div = self.divisibility(expr, divisor)?;
+		if div.factor_out().is_some() || div.reduce().is_some() { return None; }
+
+		let cases = div.cases()?;
+		Some(match cases.failing {
+			None => Verdict {
+				is_true: true,
+				conclusion: format!("lhs is divisible by rhs in all {} cases modulo {}!", cases.count, div.divisor),
+				everywhere: true,
+				..Default::default()
+			},
+			Some(residues) => Verdict {
+				is_true: false,
+				conclusion: format!("lhs isn't divisible by rhs when {} (mod {})!",
+					residues.iter().map(|(n, r)| format!("{} = {}", n, r)).collect::<Vec<String>>().join(", "),
+					div.divisor),
+				..Default::default()
+			},
+		})
+	}
+
+	/// The reductions of a divisibility: dividing both sides by
+	/// a common factor, and reducing the lhs modulo the rhs.
+	fn reduce_divisibility(&self, state: &TheoryNode) -> Vec<(Rewrite, TheoryNode)> {
+		let mut th = state;
+		while let TheoryItem::Grouping(t) = &th.item { th = t; }
+		let TheoryItem::Divisible { expr, divisor } = &th.item else { return vec![] };
+		let Some((expr, divisor)) = Self::atom_expr(expr).zip(Self::atom_expr(divisor)) else { return vec![] };
+		let Some(div) = self.divisibility(expr, divisor) else { return vec![] };
+
+		let with_sides = |poly: &Polynomial, d: &BigInt| {
+			let (lhs, rhs) = (poly.to_expr()?, Number::integer(d.clone()).to_expr()?);
+			let item = TheoryItem::Divisible { expr: Box::new(Self::atom(&lhs)), divisor: Box::new(Self::atom(&rhs)) };
+			Some((lhs, regroup_theory(&TheoryNode { token: th.token.clone(), item })))
+		};
+
+		let mut expansions = Vec::new();
+		if let Some((g, (after, next))) = div.factor_out().and_then(|(g, d)| Some((g, with_sides(&d.poly, &d.divisor)?))) {
+			let description = format!("divide both sides by {}", g);
+			expansions.push((Rewrite { description, before: expr.clone(), after, conditions: vec![] }, next));
+		}
+		if let Some((after, next)) = div.reduce().and_then(|p| with_sides(&p, &div.divisor)) {
+			let description = format!("reduce modulo {}", div.divisor);
+			expansions.push((Rewrite { description, before: expr.clone(), after, conditions: vec![] }, next));
+		}
+		expansions
+	}
+}
+
+// searching
+impl Solver {
+	/// Returns every state reachable from `state` by a single rewrite.
+	fn expand(&self, state: &TheoryNode) -> Vec<(Rewrite, TheoryNode)> {
+		let mut expansions = Vec::new();
+
+		for (pos, expr) in subexpressions(state) {
+			// groupings are transparent, their contents get visited anyway
+			if let ExprItem::Grouping(_) = expr.item { continue; }
+
+			for rule in &self.rules {
+				for rewrite in rule.apply(expr, &|name| self.is_integer(name)).into_iter().filter_map(|r| self.discharge(r)) {
+					let next = regroup_theory(&replace_at(state, &pos, &rewrite.after));
+					expansions.push((rewrite, next));
+				}
+			}
+		}
+
+		// identical simplifications are done all at once
+		for (mut rewrite, count) in Simplifier::pass(theory_expressions(state)) {
+			let positions: Vec<Position> = subexpressions(state).into_iter()
+				.filter(|(_, e)| !matches!(e.item, ExprItem::Grouping(_)) && *e == &rewrite.before)
+				.map(|(pos, _)| pos)
+				.collect();
+
+			let next = positions.iter().fold(state.clone(), |th, pos| replace_at(&th, pos, &rewrite.after));
+			if count > 1 {
+				rewrite.description = format!("{} ({}x)", rewrite.description, count);
+			}
+			expansions.push((rewrite, regroup_theory(&next)));
+		}
+
+		expansions.extend(self.reduce_divisibility(state));
+		expansions.extend(self.transform_order(state));
+
+		// polynomials are normalized as a whole, in a single step
+		for (pos, expr) in outermost_expressions(state) {
+			if let Some(after) = normalize(expr).filter(|after| *after != regroup(expr)) {
+				let next = regroup_theory(&replace_at(state, &pos, &after));
+				let rewrite = Rewrite { description: String::from("normalize"), before: expr.clone(), after, conditions: vec![] };
+				expansions.push((rewrite, next));
+			}
+		}
+
+		expansions
+	}
+
+	/// The key states are identified by in the visited set, which
+	/// doesn't depend on the order of the operands of sums and products.
+	fn state_key(state: &TheoryNode) -> String {
+		let sorted = outermost_expressions(state).into_iter()
+			.fold(state.clone(), |th, (pos, e)| replace_at(&th, &pos, &sort_operands(e)));
+		TheoryPrinter::print(&regroup_theory(&sorted))
+	}
+
+	/// Best-first search from the initial state up until a state is
+	/// found that can be decided, expanding the cheapest state first.
+	/// Returns how the search ended and the amount of states that were
+	/// expanded.
+	fn search(&self, root: TheoryNode, budget: &Budget) -> (Outcome, u64) {
+		let mut found = self.decide(&root).map(|v| (0, v));
+		let mut visited = HashSet::from([Self::state_key(&root)]);
+		let mut frontier = BinaryHeap::from([Reverse((self.cost.cost(&root), 0))]);
+		let mut nodes = vec![SearchNode { state: root, parent: 0, rewrite: None }];
+		let mut steps_tried = 0;
+		let mut gave_up = None;
+
+		'search: while found.is_none() {
+			gave_up = budget.exceeded(nodes.len(), frontier.len());
+			if gave_up.is_some() { break; }
+
+			let i = match frontier.pop() {
+				Some(Reverse((_, i))) => i,
+				None => break,
+			};
+			steps_tried += 1;
+
+			for (rewrite, state) in self.expand(&nodes[i].state) {
+				if !visited.insert(Self::state_key(&state)) { continue; }
+
+				let verdict = self.decide(&state);
+				let cost = self.cost.cost(&state);
+				nodes.push(SearchNode { state, parent: i, rewrite: Some(rewrite) });
+
+				if let Some(v) = verdict {
+					found = Some((nodes.len() - 1, v));
+					break 'search;
+				}
+				frontier.push(Reverse((cost, nodes.len() - 1)));
+			}
+		}
+
+		let outcome = match (found, gave_up) {
+			(Some((i, v)), _) => Outcome::Decided(self.steps_to(&nodes, i, TheoryNode::clone), v),
+			(None, Some(reason)) => Outcome::GaveUp(reason),
+			(None, None) => Outcome::Exhausted,
+		};
+		(outcome, steps_tried)
+	}
+
+	/// The sides of a theory that can be rewritten toward each other,
+	/// which are the expressions of a match or a definitional equality.
+	fn sides(th: &TheoryNode) -> Option<(&ExprNode, &ExprNode)> {
+		match &th.item {
+			TheoryItem::Grouping(t) => Self::sides(t),
+			TheoryItem::Implies { lhs, rhs } => Self::atom_expr(lhs).zip(Self::atom_expr(rhs)),
+			TheoryItem::Comparison { lhs, rhs } if th.token.kind == DefEquals =>
+				Self::atom_expr(lhs).zip(Self::atom_expr(rhs)),
+			_ => None,
+		}
+	}
+
+	/// Whether a side is a number or a normalized polynomial
+	/// of free unknowns, neither of which can get any simpler.
+	fn is_settled(side: &TheoryNode) -> bool {
+		Self::atom_expr(side).is_some_and(|e| evaluate(e).is_some()
+			|| is_normalized(e) && Polynomial::from_expr(e).is_some_and(|p| p.is_free()))
+	}
+
+	/// The key of a side, which is what both halves meet on.
+	fn side_key(side: &TheoryNode) -> String {
+		let mut e = Self::atom_expr(side).expect("sides are expressions");
+		while let ExprItem::Grouping(inner) = &e.item { e = inner; }
+		ExprPrinter::print(&sort_operands(e))
+	}
+
+	/// Turns an expression into a side.
+	fn atom(e: &ExprNode) -> TheoryNode {
+		regroup_theory(&TheoryNode { token: e.token.clone(), item: TheoryItem::Expression(e.clone()) })
+	}
+
+	/// Puts new sides into a match or comparison.
+	fn with_sides(th: &TheoryNode, lhs: &TheoryNode, rhs: &TheoryNode) -> TheoryNode {
+		let (lhs, rhs) = (Box::new(lhs.clone()), Box::new(rhs.clone()));
+		let item = match &th.item {
+			TheoryItem::Grouping(t) => return Self::with_sides(t, &lhs, &rhs),
+			TheoryItem::Implies { .. } => TheoryItem::Implies { lhs, rhs },
+			_ => TheoryItem::Comparison { lhs, rhs },
+		};
+		regroup_theory(&TheoryNode { token: th.token.clone(), item })
+	}
+
+	/// A state of the other half that a conclusion decides the theory with,
+	/// along with a new state of one side (0 for the lhs). The other side of
+	/// the conclusion is looked up once the new state binds all of its
+	/// metavariables, and searched for among all states otherwise.
+	fn concluding_partner(&self, root: &TheoryNode, side: usize, state: &TheoryNode, other: &Half) -> Option<usize> {
+		let expr = Self::atom_expr(state)?;
+		let whole = |j: usize| match side {
+			0 => Self::with_sides(root, state, &other.nodes[j].state),
+			_ => Self::with_sides(root, &other.nodes[j].state, state),
+		};
+
+		for (_, c) in &self.conclusions {
+			let Some((lhs, rhs)) = Self::sides(&c.theory) else { continue };
+			let (pattern, partner) = if side == 0 { (lhs, rhs) } else { (rhs, lhs) };
+			let metavariables = Metavariables { parameters: &c.parameters, is_integer: &|name| self.is_integer(name) };
+
+			for bindings in match_all(pattern, expr, metavariables) {
+				let partner = instantiate(partner, &bindings);
+				let candidates: Vec<usize> = match contains_generics(&partner) || c.parameters.iter().any(|p| !bindings.contains_key(&p.name)) {
+					true => (0..other.nodes.len()).collect(),
+					false => other.seen.get(&Self::side_key(&Self::atom(&partner))).copied().into_iter().collect(),
+				};
+				if let Some(j) = candidates.into_iter().find(|&j| self.conclude(&whole(j)).is_some()) {
+					return Some(j);
+				}
+			}
+		}
+		None
+	}
+
+	/// Searches forward from the lhs and backward from the rhs at the same
+	/// time, up until both reach the same state, settle on states that can
+	/// be compared or a conclusion decides a state of one side along with
+	/// one of the other. The proof
+	/// rewrites the lhs up until the meeting point, followed by the steps
+	/// that lead from the rhs to it.
+	fn search_sides(&self, root: &TheoryNode, lhs: &ExprNode, rhs: &ExprNode, budget: &Budget) -> (Outcome, u64) {
+		let (lhs, rhs) = (Self::atom(lhs), Self::atom(rhs));
+		let side_cost = |side: usize, state: &TheoryNode| self.cost.cost(&match side {
+			0 => Self::with_sides(root, state, &rhs),
+			_ => Self::with_sides(root, &lhs, state),
+		});
+
+		let mut halves = [Half::new(lhs.clone(), side_cost(0, &lhs)), Half::new(rhs.clone(), side_cost(1, &rhs))];
+		let mut met = halves[1].seen.get(&Self::side_key(&lhs)).map(|&j| [0, j]);
+		let mut steps_tried = 0;
+
+		'search: while met.is_none() {
+			let states = halves.iter().map(|h| h.nodes.len()).sum();
+			let frontier = halves.iter().map(|h| h.frontier.len()).sum();
+			if let Some(reason) = budget.exceeded(states, frontier) {
+				return (Outcome::GaveUp(reason), steps_tried);
+			}
+
+			// alternate between the sides, unless one of them is exhausted
+			let side = match (halves[0].frontier.is_empty(), halves[1].frontier.is_empty()) {
+				(true, true) => break,
+				(true, false) => 1,
+				(false, true) => 0,
+				(false, false) => (steps_tried % 2) as usize,
+			};
+			let Some(Reverse((_, i))) = halves[side].frontier.pop() else { break };
+			steps_tried += 1;
+
+			for (rewrite, state) in self.expand(&halves[side].nodes[i].state) {
+				let key = Self::side_key(&state);
+				if halves[side].seen.contains_key(&key) { continue; }
+
+				let cost = side_cost(side, &state);
+				let settled = Self::is_settled(&state);
+				let half = &mut halves[side];
+				half.nodes.push(SearchNode { state, parent: i, rewrite: Some(rewrite) });
+				let index = half.nodes.len() - 1;
+				half.seen.insert(key.clone(), index);
+				let pair = |other| if side == 0 { [index, other] } else { [other, index] };
+
+				if let Some(&other) = halves[1 - side].seen.get(&key) {
+					met = Some(pair(other));
+					break 'search;
+				}
+
+				// a conclusion might decide the new state along with any state of
+				// the other side, like it might any state of `search`
+				if let Some(other) = self.concluding_partner(root, side, &halves[side].nodes[index].state, &halves[1 - side]) {
+					met = Some(pair(other));
+					break 'search;
+				}
+
+				// once both sides can't get any simpler, they might be comparable
+				if settled && halves[side].settled.is_none() {
+					halves[side].settled = Some(index);
+					if let Some([a, b]) = halves[1 - side].settled.map(pair) {
+						let state = Self::with_sides(root, &halves[0].nodes[a].state, &halves[1].nodes[b].state);
+						if self.decide(&state).is_some() {
+							met = Some([a, b]);
+							break 'search;
+						}
+					}
+				}
+				halves[side].frontier.push(Reverse((cost, index)));
+			}
+		}
+
+		let Some([a, b]) = met else { return (Outcome::Exhausted, steps_tried) };
+		let (lhs_end, rhs_end) = (&halves[0].nodes[a].state, &halves[1].nodes[b].state);
+		let state = Self::with_sides(root, lhs_end, rhs_end);
+		let Some(verdict) = self.decide(&state) else { return (Outcome::Exhausted, steps_tried) };
+
+		let mut steps = self.steps_to(&halves[0].nodes, a, |s| Self::with_sides(root, s, &rhs));
+		steps.extend(self.steps_to(&halves[1].nodes, b, |s| Self::with_sides(root, lhs_end, s)));
+		(Outcome::Decided(steps, verdict), steps_tried)
+	}
+
+	/// Records a rewrite between two states, along with
+	/// the side conditions it relied on.
+	fn step(&self, rewrite: &Rewrite, before: &TheoryNode, after: &TheoryNode) -> SStep {
+		SStep {
+			description: rewrite.description.clone(),
+			process: ExprPrinter::print_rewrite(&rewrite.before, &rewrite.after),
+			state_before: TheoryPrinter::print(before),
+			state_after: TheoryPrinter::print(after),
+			conditions: rewrite.conditions.iter().map(|c| {
+				let (theory, reason) = (TheoryPrinter::print(&c.theory), c.reason.as_deref().unwrap_or("assumed"));
+				if self.markdown { format!("${}$: {}", theory, reason) } else { format!("`{}`: {}", theory, reason) }
+			}).collect(),
+		}
+	}
+
+	/// The steps that lead from the root of a search tree to one of its
+	/// nodes, with the states of the tree being put into a whole theory.
+	fn steps_to(&self, nodes: &[SearchNode], mut i: usize, whole: impl Fn(&TheoryNode) -> TheoryNode) -> Vec<SStep> {
+		let mut steps = Vec::new();
+
+		while let Some(rewrite) = &nodes[i].rewrite {
+			let parent = nodes[i].parent;
+			steps.push(self.step(rewrite, &whole(&nodes[parent].state), &whole(&nodes[i].state)));
+			i = parent;
+		}
+
+		// the path was walked from the node back to the root
+		steps.reverse();
+		steps
+	}
+}
+
+// combinations
+impl Solver {
+	fn is_combination(th: &TheoryNode) -> bool {
+		match &th.item {
+			TheoryItem::Grouping(t) => Self::is_combination(t),
+			TheoryItem::Unary(_) | TheoryItem::Logical { .. } => true,
+			_ => false,
+		}
+	}
+
+	fn shares_unknowns(lhs: &TheoryNode, rhs: &TheoryNode) -> bool {
+		let unknowns = |th| outermost_expressions(th).into_iter()
+			.flat_map(|(_, e)| free_unknowns(e))
+			.collect::<HashSet<String>>();
+		!unknowns(lhs).is_disjoint(&unknowns(rhs))
+	}
+
+	/// Proves a combination of theories by proving each of its operands
+	/// on its own, then deciding how they combine. Operands that can't
+	/// change the outcome any more aren't proven at all.
+	fn prove_combination(&self, root: &TheoryNode, budget: &Budget) -> (Outcome, u64) {
+		let mut proof = Combination::default();
+		let (truth, conclusion) = self.combine(root, budget, &mut proof);
+
+		let outcome = match truth {
+			Truth::True => Outcome::Decided(proof.steps, Verdict { is_true: true, conclusion, everywhere: true, ..Default::default() }),
+			Truth::False { everywhere } => Outcome::Decided(proof.steps, Verdict { is_true: false, conclusion, everywhere, ..Default::default() }),
+			Truth::Somewhere => Outcome::Decided(proof.steps, Verdict { is_true: true, conclusion, ..Default::default() }),
+			Truth::Unknown => Outcome::Exhausted,
+		};
+		(outcome, proof.steps_tried)
+	}
+
+	fn combine(&self, th: &TheoryNode, budget: &Budget, proof: &mut Combination) -> (Truth, String) {
+		match &th.item {
+			TheoryItem::Grouping(t) => self.combine(t, budget, proof),
+			TheoryItem::Unary(t) => match self.combine(t, budget, proof).0 {
+				Truth::True => (Truth::False { everywhere: true }, String::from("the negated theory holds!")),
+				Truth::False { everywhere: true } => (Truth::True, String::from("the negated theory never holds!")),
+				_ => self.split_cases(th, proof),
+			},
+			TheoryItem::Logical { lhs, rhs } => {
+				let or = th.token.kind == Or;
+				let a = self.combine(lhs, budget, proof).0;
+				match a {
+					Truth::True if or => return (a, String::from("lhs holds, so rhs wasn't proven!")),
+					Truth::False { .. } if !or => return (a, String::from("lhs doesn't hold, so rhs wasn't proven!")),
+					_ => {},
+				}
+
+				let b = self.combine(rhs, budget, proof).0;
+				match (a, b) {
+					(_, Truth::True) if or => (b, String::from("rhs holds!")),
+					(Truth::True, Truth::True) => (b, String::from("both lhs and rhs hold!")),
+					(_, Truth::False { .. }) if !or => (b, String::from("rhs doesn't hold!")),
+					(Truth::False { everywhere: true }, Truth::False { everywhere: true }) =>
+						(b, String::from("neither lhs nor rhs hold for any values!")),
+					// where lhs and rhs fail can be chosen independently
+					(Truth::False { .. }, Truth::False { .. }) if !Self::shares_unknowns(lhs, rhs) =>
+						(Truth::False { everywhere: false }, String::from("neither lhs nor rhs hold for all values!")),
+					_ => self.split_cases(th, proof),
+				}
+			},
+			_ => self.prove_operand(th, budget, proof),
+		}
+	}
+
+	/// Proves a single theory of a combination, adding its steps to the
+	/// proof along with a step that records how it was decided.
+	fn prove_operand(&self, th: &TheoryNode, budget: &Budget, proof: &mut Combination) -> (Truth, String) {
+		proof.operands += 1;
+		let n = proof.operands;
+		let (outcome, steps_tried) = self.prove(th, budget);
+		proof.steps_tried += steps_tried;
+
+		let (truth, conclusion, steps) = match outcome {
+			Outcome::Decided(steps, v) if v.is_true && v.everywhere => (Truth::True, v.conclusion, steps),
+			Outcome::Decided(steps, v) if v.is_true => (Truth::Somewhere, v.conclusion, steps),
+			Outcome::Decided(steps, v) => (Truth::False { everywhere: v.everywhere }, v.conclusion, steps),
+			Outcome::Exhausted => (Truth::Unknown, String::from("no proof found"), vec![]),
+			Outcome::GaveUp(reason) => (Truth::Unknown, reason, vec![]),
+		};
+
+		let state = steps.last().map(|s| s.state_after.clone()).unwrap_or_else(|| TheoryPrinter::print(th));
+		proof.steps.extend(steps.into_iter().map(|s| SStep { description: format!("operand {}: {}", n, s.description), ..s }));
+		let value = match truth {
+			Truth::True => "true",
+			Truth::False { .. } => "false",
+			Truth::Somewhere => "true for some values",
+			Truth::Unknown => "unknown",
+		};
+		proof.steps.push(SStep {
+			description: format!("decide operand {}: {}", n, conclusion),
+			process: TheoryPrinter::print_decision(th, value),
+			state_before: state,
+			state_after: String::from(value),
+			conditions: vec![],
+		});
+		(truth, conclusion)
+	}
+
+	/// Decides a combination of theories that share unknowns case by case.
+	fn split_cases(&self, th: &TheoryNode, proof: &mut Combination) -> (Truth, String) {
+		let Some(cases) = cases::split(th, self.tolerance.get(), |name| self.annotation(name)) else {
+			return (Truth::Unknown, String::from("the operands couldn't be combined!"));
+		};
+
+		let state = TheoryPrinter::print(th);
+		for (i, case) in cases.iter().enumerate() {
+			let value = if case.holds { "true" } else { "false" };
+			proof.steps.push(SStep {
+				description: format!("case {}: {}", i + 1, case.description),
+				process: TheoryPrinter::print_decision(th, value),
+				state_before: state.clone(),
+				state_after: String::from(value),
+				conditions: vec![],
+			});
+			if !case.holds {
+				return (Truth::False { everywhere: false }, format!("the theory doesn't hold when {}!", case.description));
+			}
+		}
+		(Truth::True, format!("the theory holds in all {} cases!", cases.len()))
+	}
+}
+
+// side conditions
+impl Solver {
+	/// Decides a theory, or searches for a proof of it with the strategy
+	/// of the solver if it can't be decided right away.
+	fn prove(&self, root: &TheoryNode, budget: &Budget) -> (Outcome, u64) {
+		if Self::is_combination(root) {
+			return self.prove_combination(root, budget);
+		}
+		match self.decide(root) {
+			Some(v) => (Outcome::Decided(vec![], v), 0),
+			None => match Self::sides(root) {
+				Some((lhs, rhs)) if self.strategy == Strategy::EGraph => self.saturate(root, lhs, rhs, budget),
+				Some((lhs, rhs)) => self.search_sides(root, lhs, rhs, budget),
+				None => self.search(root.clone(), budget),
+			},
+		}
+	}
+
+	/// Proves a side condition, by deciding it or as a sub-goal.
+	/// Returns why it holds, or `None` if it can't be shown to.
+	fn discharge_condition(&self, condition: &TheoryNode) -> Option<String> {
+		let key = TheoryPrinter::print(condition);
+		if let Some(reason) = self.discharged.borrow().get(&key) {
+			return reason.clone();
+		}
+
+		let depth = self.depth.get();
+		let reason = if depth < MAX_GUARD_DEPTH {
+			self.depth.set(depth + 1);
+			let budget = Budget::new(Limits { max_states: Some(MAX_GUARD_STATES), ..Limits::default() });
+			let (outcome, _) = self.prove(condition, &budget);
+			self.depth.set(depth);
+
+			match outcome {
+				Outcome::Decided(steps, v) if v.is_true && steps.is_empty() => Some(v.conclusion),
+				Outcome::Decided(steps, v) if v.is_true => Some(format!("{} (after {} steps)", v.conclusion, steps.len())),
+				_ => None,
+			}
+		} else {
+			None
+		};
+
+		// failing deeper down doesn't mean failing closer to the question
+		if reason.is_some() || depth == 0 {
+			self.discharged.borrow_mut().insert(key, reason.clone());
+		}
+		reason
+	}
+
+	/// Discharges the side conditions of a rewrite, returning
+	/// `None` if any of them can't be shown to hold.
+	fn discharge(&self, mut rewrite: Rewrite) -> Option<Rewrite> {
+		for condition in &mut rewrite.conditions {
+			condition.reason = Some(self.discharge_condition(&condition.theory)?);
+		}
+		Some(rewrite)
+	}
+}
+
+// saturating
+impl Solver {
+	/// Every rewrite of the expression as a whole.
+	fn rewrites(&self, expr: &ExprNode) -> Vec<Rewrite> {
+		let mut rewrites: Vec<Rewrite> = self.rules.iter()
+			.flat_map(|r| r.apply(expr, &|name| self.is_integer(name)))
+			.filter_map(|r| self.discharge(r))
+			.collect();
+
+		rewrites.extend(Simplifier::pass([expr]).into_iter()
+			.map(|(rewrite, _)| rewrite)
+			.filter(|rewrite| rewrite.before == *expr));
+
+		if let Some(after) = normalize(expr).filter(|after| after != expr) {
+			rewrites.push(Rewrite { description: String::from("normalize"), before: expr.clone(), after, conditions: vec![] });
+		}
+		rewrites
+	}
+
+	/// The expressions of a node with its children replaced by other
+	/// members of their classes, starting with the node's own expression.
+	fn variants(graph: &EGraph, members: &HashMap<usize, Vec<usize>>, classes: &[usize], node: usize) -> Vec<ExprNode> {
+		let mut combinations = vec![vec![]];
+		for &child in graph.children(node) {
+			let options: Vec<usize> = std::iter::once(child)
+				.chain(members[&classes[child]].iter().copied().filter(|&m| m != child))
+				.take(MAX_MEMBERS)
+				.collect();
+
+			combinations = combinations.iter()
+				.flat_map(|c| options.iter().map(move |&o| [c.as_slice(), &[o]].concat()))
+				.take(MAX_VARIANTS)
+				.collect();
+		}
+
+		combinations.into_iter()
+			.map(|c| regroup(&with_children(graph.term(node), c.iter().map(|&m| graph.term(m).clone()).collect())))
+			.collect()
+	}
+
+	/// The simplest settled members of the classes of both sides,
+	/// if putting them into the theory decides it.
+	fn settled_pair(&self, root: &TheoryNode, graph: &EGraph, members: &HashMap<usize, Vec<usize>>, sides: [usize; 2]) -> Option<[usize; 2]> {
+		let settled = |side: usize| members[&graph.find(side)].iter().copied()
+			.find(|&m| Self::is_settled(&Self::atom(graph.term(m))));
+
+		let (a, b) = (settled(sides[0])?, settled(sides[1])?);
+		let state = Self::with_sides(root, &Self::atom(graph.term(a)), &Self::atom(graph.term(b)));
+		self.decide(&state).map(|_| [a, b])
+	}
+
+	/// Members of the classes of both sides that a conclusion decides the
+	/// theory with, which are found like the states of `search_sides`.
+	fn concluded_pair(&self, root: &TheoryNode, graph: &EGraph, members: &HashMap<usize, Vec<usize>>, sides: [usize; 2]) -> Option<[usize; 2]> {
+		if self.conclusions.is_empty() { return None; }
+
+		// the members of the rhs class, as if they were the rhs half of a search
+		let rhs_members = &members[&graph.find(sides[1])];
+		let nodes: Vec<SearchNode> = rhs_members.iter()
+			.map(|&m| SearchNode { state: Self::atom(graph.term(m)), parent: 0, rewrite: None })
+			.collect();
+		let seen = nodes.iter().enumerate().map(|(j, n)| (Self::side_key(&n.state), j)).collect();
+		let rhs_half = Half { nodes, seen, frontier: BinaryHeap::new(), settled: None };
+
+		members[&graph.find(sides[0])].iter().find_map(|&m| {
+			let j = self.concluding_partner(root, 0, &Self::atom(graph.term(m)), &rhs_half)?;
+			Some([m, rhs_members[j]])
+		})
+	}
+
+	/// Equality saturation: puts both sides into an e-graph and rewrites
+	/// every node with every rule, up until both sides end up in the same
+	/// class, they settle on expressions that can be compared, a conclusion
+	/// decides members of both classes or nothing new is found. Rules are applied to the expressions the nodes were
+	/// built from, as well as to those with their children replaced by
+	/// other members of their classes. The proof is extracted from the
+	/// rewrites that merged the classes, like that of `search_sides`.
+	fn saturate(&self, root: &TheoryNode, lhs: &ExprNode, rhs: &ExprNode, budget: &Budget) -> (Outcome, u64) {
+		let mut graph = EGraph::default();
+		let sides = [graph.add(lhs), graph.add(rhs)];
+		let mut tried = HashSet::new();
+		let mut steps_tried = 0;
+		let mut saturated = false;
+
+		let met = loop {
+			// the members of each class, simplest first
+			let mut members = graph.classes();
+			for class in members.values_mut() {
+				class.sort_by_cached_key(|&m| ExprPrinter::print(graph.term(m)).len());
+			}
+
+			// the lhs is rewritten all the way into the rhs
+			if graph.find(sides[0]) == graph.find(sides[1]) {
+				break Some([sides[1], sides[1]]);
+			}
+			if let Some(pair) = self.settled_pair(root, &graph, &members, sides) {
+				break Some(pair);
+			}
+			if let Some(pair) = self.concluded_pair(root, &graph, &members, sides) {
+				break Some(pair);
+			}
+			if saturated { break None; }
+
+			// the classes as they were at the start of this round
+			let classes: Vec<usize> = (0..graph.len()).map(|n| graph.find(n)).collect();
+			let mut progress = false;
+			for node in 0..classes.len() {
+				if let Some(reason) = budget.exceeded(graph.len(), 0) {
+					return (Outcome::GaveUp(reason), steps_tried);
+				}
+
+				for expr in Self::variants(&graph, &members, &classes, node) {
+					if !tried.insert(ExprPrinter::print(&expr)) { continue; }
+					progress = true;
+					steps_tried += 1;
+
+					let from = graph.add(&expr);
+					for rewrite in self.rewrites(&expr) {
+						let to = graph.add(&rewrite.after);
+						graph.union_rewrite(from, to, rewrite);
+					}
+				}
+			}
+
+			saturated = !progress;
+			graph.rebuild();
+		};
+
+		let Some([a, b]) = met else { return (Outcome::Exhausted, steps_tried) };
+		let rhs = Self::atom(rhs);
+		let (lhs_end, rhs_end) = (Self::atom(graph.term(a)), Self::atom(graph.term(b)));
+		let Some(verdict) = self.decide(&Self::with_sides(root, &lhs_end, &rhs_end)) else {
+			return (Outcome::Exhausted, steps_tried);
+		};
+
+		let mut steps = self.explained(&graph, sides[0], a, |s| Self::with_sides(root, s, &rhs));
+		steps.extend(self.explained(&graph, sides[1], b, |s| Self::with_sides(root, &lhs_end, s)));
+		(Outcome::Decided(steps, verdict), steps_tried)
+	}
+
+	/// The steps that lead from one node of the e-graph to another
+	/// one of its class, with the expressions put into a whole theory.
+	fn explained(&self, graph: &EGraph, from: usize, to: usize, whole: impl Fn(&TheoryNode) -> TheoryNode) -> Vec<SStep> {
+		let mut before = whole(&Self::atom(graph.term(from)));
+		graph.explain(from, to).into_iter()
+			.map(|(rewrite, expr)| {
+				let after = whole(&Self::atom(&expr));
+				let step = self.step(&rewrite, &before, &after);
+				before = after;
+				step
+			})
+			.collect()
+	}
+}
+
+// public stuff
+impl Solver {
+	pub fn new(context: &Context) -> Self {
+		let mut rules: Vec<Box<dyn Rule>> = vec![Box::new(Substitute)];
+
+		for (path, theorem) in context.all_theorems() {
+			rules.push(Box::new(ApplyTheorem { path, theorem: theorem.clone() }));
+		}
+
+		let conclusions = context.all_conclusions().into_iter()
+			.map(|(path, c)| (path, c.clone()))
+			.collect();
+
+		Self {
+			rules,
+			conclusions,
+			cost: Box::new(Combined::default()),
+			limits: Limits::default(),
+			strategy: Strategy::default(),
+			depth: Cell::new(0),
+			discharged: RefCell::new(HashMap::new()),
+			unknowns: context.unknowns.iter()
+				.filter_map(|(name, p)| Some((name.clone(), p.annotation?)))
+				.collect(),
+			parameters: RefCell::new(Vec::new()),
+			default_tolerance: TOLERANCE,
+			tolerance: Cell::new(TOLERANCE),
+			markdown: false,
+		}
+	}
+
+	/// Sets which states are expanded first.
+	pub fn with_cost(mut self, cost: Box<dyn Cost>) -> Self {
+		self.cost = cost;
+		self
+	}
+
+	/// Sets the limits of questions that don't set their own.
+	pub fn with_limits(mut self, limits: Limits) -> Self {
+		self.limits = limits;
+		self
+	}
+
+	/// Sets how far apart the sides of `~=` may be in questions that don't say.
+	pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+		self.default_tolerance = tolerance;
+		self
+	}
+
+	/// Sets how questions with two sides are proven.
+	pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+		self.strategy = strategy;
+		self
+	}
+
+	/// Sets whether side conditions are written for markdown.
+	pub fn with_markdown(mut self, markdown: bool) -> Self {
+		self.markdown = markdown;
+		self
+	}
+
+	/// Applies the settings given on the command line.
+	pub fn with_args(self, args: &CliArgs) -> Self {
+		let limits = Limits { max_states: args.max_states, timeout: args.timeout, max_frontier: args.max_frontier };
+		self.with_limits(limits)
+			.with_tolerance(args.tolerance.unwrap_or(TOLERANCE))
+			.with_strategy(args.strategy.unwrap_or_default())
+			.with_cost(cost::from_model(args.cost.unwrap_or_default()))
+			.with_markdown(args.markdown)
+	}
+
+	pub fn solve(&self, question: &Question) -> SQuestion {
+		let root = regroup_theory(&question.theory);
+		let limits = question.limits.or(&self.limits);
+		*self.parameters.borrow_mut() = question.parameters.clone();
+		self.tolerance.set(question.tolerance.unwrap_or(self.default_tolerance));
+		let (outcome, steps_tried) = self.prove(&root, &Budget::new(limits));
+
+		let (mut steps, mut conclusion, mut answer, mut answer_type, solutions) = match outcome {
+			Outcome::Decided(steps, v) => (
+				steps,
+				v.conclusion,
+				String::from(if v.is_true { "correct" } else { "incorrect" }),
+				if v.is_true { AnswerType::True } else { AnswerType::False },
+				v.solutions.unwrap_or_default(),
+			),
+			Outcome::Exhausted => (vec![], String::from("no proof found"), String::from("unproven"), AnswerType::Unknown, Solutions::default()),
+			Outcome::GaveUp(reason) => (vec![], reason, String::from("gave up"), AnswerType::Unknown, Solutions::default()),
+		};
+
+		// a question that isn't proven might be disproven by example
+		let counterexample = match answer_type {
+			AnswerType::True => None,
+			_ => falsify(&root, self.tolerance.get(), |name| self.annotation(name)),
+		};
+		if let Some(assignment) = &counterexample {
+			if answer_type == AnswerType::Unknown {
+				steps.clear();
+				conclusion = format!("the theory doesn't hold for {}!", format_assignment(assignment));
+				answer = String::from("incorrect");
+				answer_type = AnswerType::False;
+			}
+		}
+
+		SQuestion {
+			name: question.name.clone(),
+			theory: TheoryPrinter::print(&question.theory),
+			steps,
+			conclusion,
+			answer,
+			answer_type,
+			counterexample: counterexample.iter().flatten().map(|(n, v)| format!("{} = {}", n, v)).collect(),
+			solutions: solutions.values,
+			solution_set: solutions.complete,
+			steps_tried,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::parse;
+
+	fn answer(src: &str) -> AnswerType {
+		let script = parse(src);
+		Solver::new(&script.context).solve(script.question("q")).answer_type
+	}
+
+	#[test]
+	fn proves_an_arithmetic_identity() {
+		assert_eq!(answer("?q := 2 + 3 == 5"), AnswerType::True);
+	}
+
+	#[test]
+	fn disproves_a_wrong_identity() {
+		assert_eq!(answer("?q := 2 * 3 == 5"), AnswerType::False);
+	}
+
+	#[test]
+	fn rounding_errors_dont_decide_comparisons() {
+		assert_eq!(answer("?q := sqrt(2) * sqrt(2) == 2"), AnswerType::Unknown);
+		assert_eq!(answer("?q := sqrt(2)^2 <= 2"), AnswerType::Unknown);
+		assert_eq!(answer("?q := sqrt(2) < 1.5"), AnswerType::True);
+		assert_eq!(answer("?q := sqrt(2) * sqrt(2) == 3"), AnswerType::False);
+	}
+
+	#[test]
+	fn rewrites_both_sides_toward_each_other() {
+		let script = parse("!add_zero := ..0 + 0 := ..0\n!mul_one := ..0 * 1 := ..0\n?q(x: real) := x + 0 <=> x * 1");
+		let solved = Solver::new(&script.context).solve(script.question("q"));
+		let steps: Vec<_> = solved.steps.iter().map(|s| (s.description.as_str(), s.state_after.as_str())).collect();
+		assert_eq!(solved.answer_type, AnswerType::True);
+		// the lhs is rewritten up to the meeting point, followed by the rhs
+		assert_eq!(steps, [
+			("rewrite using `!add_zero`", "x <=> (x * 1)"),
+			("rewrite using `!mul_one`", "x <=> x"),
+		]);
+	}
+
+	#[test]
+	fn conclusions_decide_rewritten_sides() {
+		let src = "&pyth t := sin(..0)^2 + cos(..0)^2 == 1\n!mul_one := ..0 * 1 := ..0\n?q(x: real) := sin(x)^2 + cos(x * 1)^2 == 1";
+		for strategy in [Strategy::Search, Strategy::EGraph] {
+			let script = parse(src);
+			let solved = Solver::new(&script.context).with_strategy(strategy).solve(script.question("q"));
+			assert_eq!(solved.answer_type, AnswerType::True);
+			assert_eq!(solved.steps.len(), 1);
+		}
+	}
+}
@@ -0,0 +1,418 @@
+use super::{
+	eval::{Number, evaluate, evaluate_equation},
+	interval::{approximately_equal, compare},
+	rewrite::{children, with_children, outermost_expressions, replace_at},
+	pattern::{Bindings, instantiate},
+	types::Type,
+};
+use crate::{
+	parse::ast::{ExprNode, ExprItem, Theorem, TheoryNode, TheoryItem},
+	lex::token::TokenKind::{Or, NotImplies, DefEquals, DefNotEquals, Greater, GreaterEqual, Lesser, LesserEqual, RoughlyEquals},
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use std::{cmp::Ordering, collections::{BTreeMap, BTreeSet}};
+
+/// How many random points a theorem or question is tested at.
+const SAMPLES: usize = 64;
+/// How many integer points a question is tested at before random ones.
+const MAX_INTEGER_POINTS: usize = 1_000;
+/// How deeply function calls are substituted before giving up on a point.
+const MAX_CALL_DEPTH: usize = 32;
+/// The largest magnitude of the integers that are picked.
+const MAX_SAMPLE: i64 = 10;
+
+/// Values for the unknowns of a theorem or question.
+pub type Assignment = Vec<(String, Number)>;
+
+pub fn format_assignment(assignment: &Assignment) -> String {
+	assignment.iter().map(|(n, v)| format!("{} = {}", n, v)).collect::<Vec<String>>().join(", ")
+}
+
+/// A point at which the sides of a theorem differ.
+pub struct Counterexample {
+	pub assignment: Assignment,
+	pub lhs: String,
+	pub rhs: String,
+}
+
+impl std::fmt::Display for Counterexample {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self.assignment.is_empty() {
+			true => write!(f, "lhs is {}, rhs is {}", self.lhs, self.rhs),
+			false => write!(f, "{} makes lhs {} and rhs {}", format_assignment(&self.assignment), self.lhs, self.rhs),
+		}
+	}
+}
+
+/// A small deterministic generator (xorshift64*), so that
+/// the same theorem is always tested at the same points.
+struct Random(u64);
+
+impl Random {
+	fn new(seed: &str) -> Self {
+		// FNV-1a, which is never zero for our purposes
+		let hash = seed.bytes().fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+		Self(hash | 1)
+	}
+
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 >> 12;
+		self.0 ^= self.0 << 25;
+		self.0 ^= self.0 >> 27;
+		self.0.wrapping_mul(0x2545f4914f6cdd1d)
+	}
+
+	fn integer(&mut self, min: i64, max: i64) -> i64 {
+		min + (self.next() % (max - min + 1) as u64) as i64
+	}
+
+	/// A small integer, or for reals a small fraction half of the time.
+	fn number(&mut self, annotation: Option<Type>) -> Number {
+		let numer = BigInt::from(self.integer(-MAX_SAMPLE, MAX_SAMPLE));
+		let denom = match annotation {
+			Some(Type::Int) => 1,
+			_ if self.next() & 1 == 0 => 1,
+			_ => self.integer(2, 7),
+		};
+		Number::Exact(BigRational::new(numer, BigInt::from(denom)))
+	}
+}
+
+/// Either side of a theorem evaluated at a point.
+enum Value {
+	Number(Number),
+	Truth(bool),
+}
+
+impl Value {
+	/// Whether two values differ by more than `tolerance`, relative to
+	/// the larger of them if that's more than one.
+	fn differs(&self, other: &Value, tolerance: f64) -> bool {
+		match (self, other) {
+			(Value::Number(Number::Exact(a)), Value::Number(Number::Exact(b))) => a != b,
+			(Value::Number(a), Value::Number(b)) => {
+				let (a, b) = (a.to_f64(), b.to_f64());
+				(a - b).abs() > tolerance * a.abs().max(b.abs()).max(1.0)
+			},
+			(Value::Truth(a), Value::Truth(b)) => a != b,
+			_ => false,
+		}
+	}
+}
+
+impl std::fmt::Display for Value {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Value::Number(n) => write!(f, "{}", n),
+			Value::Truth(t) => write!(f, "{}", t),
+		}
+	}
+}
+
+fn key(expr: &ExprNode) -> Option<String> {
+	match &expr.item {
+		ExprItem::Symbol(name) => Some(name.clone()),
+		ExprItem::Generic(Some(i)) => Some(format!("..{}", i)),
+		ExprItem::Generic(None) => Some(String::from("..")),
+		_ => None,
+	}
+}
+
+/// The unknowns and generics of an expression, including those
+/// of the variables it uses.
+pub fn free_unknowns(expr: &ExprNode) -> BTreeSet<String> {
+	let mut names = BTreeSet::new();
+	unknowns(expr, &mut names);
+	names
+}
+
+fn unknowns(expr: &ExprNode, out: &mut BTreeSet<String>) {
+	//! the bodies of called functions only use their own parameters
+	if let Some(name) = key(expr) {
+		out.insert(name);
+	}
+	if let ExprItem::Variable { expr: definition, .. } = &expr.item {
+		unknowns(definition, out);
+	}
+	for child in children(expr) {
+		unknowns(child, out);
+	}
+}
+
+/// Replaces the unknowns by their values, and variables
+/// and calls by what they're defined as.
+fn substitute(expr: &ExprNode, values: &BTreeMap<String, Number>, depth: usize) -> Option<ExprNode> {
+	if let Some(name) = key(expr) {
+		return values.get(&name)?.to_expr();
+	}
+
+	match &expr.item {
+		ExprItem::Variable { expr: definition, .. } => substitute(definition, values, depth),
+		ExprItem::Call { args, function, .. } => {
+			if depth >= MAX_CALL_DEPTH { return None; }
+
+			let bindings: Bindings = function.parameters.iter()
+				.map(|p| p.name.clone())
+				.zip(args.iter().map(|a| substitute(a, values, depth)).collect::<Option<Vec<ExprNode>>>()?)
+				.collect();
+			substitute(&instantiate(&function.body, &bindings), values, depth + 1)
+		},
+		_ => {
+			let children = children(expr).into_iter()
+				.map(|c| substitute(c, values, depth))
+				.collect::<Option<Vec<ExprNode>>>()?;
+			Some(with_children(expr, children))
+		},
+	}
+}
+
+fn closed_value(expr: &ExprNode) -> Option<Value> {
+	match evaluate_equation(expr) {
+		Some(truth) => Some(Value::Truth(truth)),
+		None => evaluate(expr).map(Value::Number),
+	}
+}
+
+/// The value of an expression for the given values of its unknowns.
+pub fn value_at(expr: &ExprNode, values: &BTreeMap<String, Number>) -> Option<Number> {
+	evaluate(&substitute(expr, values, 0)?)
+}
+
+/// How the values of two expressions compare for the given values of
+/// their unknowns, if rounding doesn't keep it from being told.
+pub fn compare_at(lhs: &ExprNode, rhs: &ExprNode, values: &BTreeMap<String, Number>) -> Option<Ordering> {
+	compare(&substitute(lhs, values, 0)?, &substitute(rhs, values, 0)?)
+}
+
+fn value(expr: &ExprNode, values: &BTreeMap<String, Number>) -> Option<Value> {
+	closed_value(&substitute(expr, values, 0)?)
+}
+
+fn substitute_theory(th: &TheoryNode, values: &BTreeMap<String, Number>) -> Option<TheoryNode> {
+	outermost_expressions(th).into_iter()
+		.try_fold(th.clone(), |th, (pos, e)| Some(replace_at(&th, &pos, &substitute(e, values, 0)?)))
+}
+
+fn expr(th: &TheoryNode) -> Option<&ExprNode> {
+	match &th.item {
+		TheoryItem::Expression(e) => Some(e),
+		TheoryItem::Grouping(g) => match &g.item {
+			TheoryItem::Expression(e) => Some(e),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+/// Whether a (closed) theory holds, if it can be told by evaluation.
+/// A question's `!=` and `<!>` only claim that its sides aren't the same
+/// everywhere, which no single point can disprove, so with `universal` set
+/// those can't be told. The operands of `~`, `&` and `|` are claims about
+/// each single point, as they are to the solver.
+fn holds(th: &TheoryNode, universal: bool, tolerance: f64) -> Option<bool> {
+	let atom = |t: &TheoryNode| closed_value(expr(t)?);
+	let side = |t: &TheoryNode| match atom(t)? {
+		Value::Number(n) => Some(n),
+		Value::Truth(_) => None,
+	};
+
+	match &th.item {
+		TheoryItem::Grouping(t) => holds(t, universal, tolerance),
+		TheoryItem::Unary(t) => Some(!holds(t, false, tolerance)?),
+		TheoryItem::Logical { lhs, rhs } => {
+			let (a, b) = (holds(lhs, false, tolerance)?, holds(rhs, false, tolerance)?);
+			Some(if th.token.kind == Or { a || b } else { a && b })
+		},
+		TheoryItem::Implies { .. } if universal && th.token.kind == NotImplies => None,
+		TheoryItem::Implies { lhs, rhs } => {
+			let same = match (atom(lhs), atom(rhs)) {
+				(Some(a), Some(b)) => !a.differs(&b, tolerance),
+				_ => holds(lhs, universal, tolerance)? == holds(rhs, universal, tolerance)?,
+			};
+			Some(same != (th.token.kind == NotImplies))
+		},
+		TheoryItem::Comparison { .. } if universal && th.token.kind == DefNotEquals => None,
+		TheoryItem::Comparison { lhs, rhs } if th.token.kind == RoughlyEquals =>
+			approximately_equal(expr(lhs)?, expr(rhs)?, &BigRational::from_float(tolerance)?),
+		TheoryItem::Comparison { lhs, rhs } => {
+			// rounded sides can only be told apart by the bounds of their errors
+			let order = compare(expr(lhs)?, expr(rhs)?)?;
+			match th.token.kind {
+				DefEquals => Some(order.is_eq()),
+				DefNotEquals => Some(order.is_ne()),
+				Greater => Some(order.is_gt()),
+				GreaterEqual => Some(order.is_ge()),
+				Lesser => Some(order.is_lt()),
+				LesserEqual => Some(order.is_le()),
+				_ => None,
+			}
+		},
+		TheoryItem::Divisible { expr, divisor } => side(expr)?.is_multiple_of(&side(divisor)?),
+		TheoryItem::Expression(e) => evaluate_equation(e),
+		TheoryItem::Exists(_) => None,
+	}
+}
+
+/// Whether a theory holds for the given values of its unknowns, if it can
+/// be told by evaluation.
+pub fn holds_at(th: &TheoryNode, values: &BTreeMap<String, Number>, tolerance: f64) -> Option<bool> {
+	holds(&substitute_theory(th, values)?, false, tolerance)
+}
+
+/// Evaluates both sides of a theorem at random points of its unknowns and
+/// generics, skipping points where its guard doesn't hold or either side
+/// is undefined. Returns the first point where the sides differ, if any.
+/// `annotation` gives the type of an unknown, if it has one.
+pub fn counterexample(theorem: &Theorem, tolerance: f64, annotation: impl Fn(&str) -> Option<Type>) -> Option<Counterexample> {
+	let mut names = BTreeSet::new();
+	unknowns(&theorem.lhs, &mut names);
+	unknowns(&theorem.rhs, &mut names);
+
+	let mut random = Random::new(&theorem.name);
+	for _ in 0..SAMPLES {
+		let values: BTreeMap<String, Number> = names.iter()
+			.map(|n| (n.clone(), random.number(annotation(n))))
+			.collect();
+
+		if let Some(guard) = &theorem.guard {
+			let guard = substitute_theory(guard, &values);
+			if guard.and_then(|g| holds(&g, false, tolerance)) != Some(true) { continue; }
+		}
+
+		let (Some(lhs), Some(rhs)) = (value(&theorem.lhs, &values), value(&theorem.rhs, &values)) else { continue };
+		if lhs.differs(&rhs, tolerance) {
+			return Some(Counterexample {
+				assignment: values.into_iter().collect(),
+				lhs: lhs.to_string(),
+				rhs: rhs.to_string(),
+			});
+		}
+
+		// without any unknowns, one point is as good as any other
+		if names.is_empty() { break; }
+	}
+
+	None
+}
+
+/// All points of `n` integers, by increasing magnitude, up to `max` of them.
+pub fn integer_points(n: usize, max: usize) -> Vec<Vec<i64>> {
+	let mut points = Vec::new();
+
+	for r in 0..=MAX_SAMPLE {
+		// every point within the radius, of which one value is on it
+		let mut point = vec![-r; n];
+		loop {
+			if point.iter().any(|v| v.abs() == r) {
+				points.push(point.clone());
+				if points.len() >= max { return points; }
+			}
+			let Some(i) = point.iter().rposition(|&v| v < r) else { break };
+			point[i] += 1;
+			point[i + 1..].fill(-r);
+		}
+	}
+	points
+}
+
+/// Looks for values of the unknowns of a question for which it doesn't
+/// hold: small integers first, followed by random (fractional) values.
+/// `annotation` gives the type of an unknown, if it has one.
+pub fn falsify(th: &TheoryNode, tolerance: f64, annotation: impl Fn(&str) -> Option<Type>) -> Option<Assignment> {
+	let mut names = BTreeSet::new();
+	for (_, e) in outermost_expressions(th) {
+		unknowns(e, &mut names);
+	}
+	if names.is_empty() { return None; }
+
+	let fails = |values: &BTreeMap<String, Number>| substitute_theory(th, values)
+		.and_then(|th| holds(&th, true, tolerance)) == Some(false);
+
+	let integers = integer_points(names.len(), MAX_INTEGER_POINTS).into_iter()
+		.map(|point| names.iter().cloned().zip(point.into_iter().map(Number::integer)).collect());
+
+	let mut random = Random::new(&names.iter().cloned().collect::<String>());
+	let samples = (0..SAMPLES).map(|_| names.iter()
+		.map(|n| (n.clone(), random.number(annotation(n))))
+		.collect());
+
+	integers.chain(samples)
+		.find(|values: &BTreeMap<String, Number>| fails(values))
+		.map(|values| values.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{runtime::question::TOLERANCE, testing::parse};
+
+	/// A counterexample to the theorem `!t`, with `x` an integer if `int` is set.
+	fn unsound(src: &str, int: bool) -> Option<Counterexample> {
+		let script = parse(src);
+		let annotation = |name: &str| (int && name == "x").then_some(Type::Int);
+		counterexample(script.theorem("t"), TOLERANCE, annotation)
+	}
+
+	#[test]
+	fn sound_theorems_have_no_counterexample() {
+		assert!(unsound("$x: real\n!t := x^2 + ..0 x := (x + ..0 / 2)^2 - (..0 / 2)^2", false).is_none());
+		assert!(unsound("!t := (..0 + ..1)^2 := ..0^2 + 2 ..0 ..1 + ..1^2", false).is_none());
+	}
+
+	#[test]
+	fn typos_are_caught() {
+		let found = unsound("$x: real\n!t := x^1 + ..0 x := (x + ..0 / 2)^2 - (..0 / 2)^2", false);
+		assert!(found.is_some_and(|c| c.lhs != c.rhs));
+	}
+
+	#[test]
+	fn points_outside_the_guard_are_skipped() {
+		assert!(unsound("!t := abs ..0 := ..0 | ..0 >= 0", false).is_none());
+		assert!(unsound("!t := abs ..0 := ..0", false).is_some());
+	}
+
+	#[test]
+	fn integers_are_tested_at_integers() {
+		assert!(unsound("$x: real\n!t := floor x := x", true).is_none());
+		assert!(unsound("$x: real\n!t := floor x := x", false).is_some());
+	}
+
+	#[test]
+	fn closed_theorems_are_evaluated() {
+		let found = unsound("!t := 2 + 2 := 5", false).unwrap();
+		assert_eq!(found.to_string(), "lhs is 4, rhs is 5");
+	}
+
+	/// Values for which the question `?q` doesn't hold, as in `x = 0`.
+	fn falsified(src: &str, int: bool) -> Option<String> {
+		let script = parse(src);
+		let annotation = |name: &str| (int && name == "x").then_some(Type::Int);
+		falsify(script.theory("q"), TOLERANCE, annotation).map(|a| format_assignment(&a))
+	}
+
+	#[test]
+	fn small_integers_are_tried_first() {
+		assert_eq!(falsified("$x: real\n?q := x^2 > x", false), Some("x = 0".to_string()));
+		assert_eq!(falsified("$x: real\n$y: real\n?q := x + y == y", false), Some("x = -1, y = -1".to_string()));
+	}
+
+	#[test]
+	fn fractions_are_tried_for_reals() {
+		assert!(falsified("$x: real\n?q := x % 1", false).is_some());
+		assert_eq!(falsified("$x: real\n?q := x % 1", true), None);
+	}
+
+	#[test]
+	fn true_theories_are_not_falsified() {
+		assert_eq!(falsified("$x: real\n?q := (x + 1)^2 >= 0", false), None);
+		assert_eq!(falsified("?q := 1 == 2", false), None);
+	}
+
+	#[test]
+	fn rounding_errors_are_not_counterexamples() {
+		assert_eq!(falsified("$x: real\n?q := sin(2 x) == 2 sin(x) cos(x)", false), None);
+		assert_eq!(falsified("$x: real\n?q := exp(x) * exp(x) == exp(2 x)", false), None);
+		assert_eq!(falsified("$x: real\n?q := sin(x) == 5", false), Some("x = 0".to_string()));
+	}
+}
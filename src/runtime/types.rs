@@ -1,24 +1,25 @@
-pub trait Type {
-	fn to_string(&self) -> &str;
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Type {
+	Int,
+	Real,
 }
 
-impl dyn Type {
-	pub fn from_string(string: String) -> Option<impl Type> {
-		match string.as_str() {
-			"int" => Some(Int),
+impl Type {
+	pub fn from_string(string: &str) -> Option<Self> {
+		match string {
+			"int" => Some(Type::Int),
+			"real" => Some(Type::Real),
 			
 			_ => None
 		}
 	}
 }
 
-macro_rules! __to_string {
-	($ret:expr) => {
-		fn to_string(&self) -> &str { $ret }
-	};
-}
-
-struct Int;
-impl Type for Int {
-	__to_string!{"int"}
+impl std::fmt::Display for Type {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Type::Int => write!(f, "int"),
+			Type::Real => write!(f, "real"),
+		}
+	}
 }
\ No newline at end of file
@@ -0,0 +1,74 @@
+//! Helpers for the unit tests, which parse their scripts from strings.
+
+use crate::{
+	SOURCES,
+	cli::set_cli_args_empty,
+	lex::Lexer,
+	parse::{Parser, Context, ast::{ExprNode, TheoryNode, Theorem}},
+	runtime::question::Question,
+};
+use std::sync::{Mutex, MutexGuard};
+
+/// The sources and command line arguments are global, and tokens point
+/// into the sources, so only one test may use them at a time.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// A parsed script, which keeps other tests from parsing until it's dropped.
+pub struct Script {
+	pub context: Context,
+	_lock: MutexGuard<'static, ()>,
+}
+
+impl Script {
+	/// The definition of the variable `name`.
+	pub fn variable(&self, name: &str) -> &ExprNode {
+		self.context.get_variable(name.to_string())
+			.unwrap_or_else(|| panic!("no variable `{}`", name))
+	}
+
+	/// The theorem `name`.
+	pub fn theorem(&self, name: &str) -> &Theorem {
+		self.context.get_theorem(name.to_string())
+			.unwrap_or_else(|| panic!("no theorem `{}`", name))
+	}
+
+	/// The question `name`.
+	pub fn question(&self, name: &str) -> &Question {
+		self.context.questions.iter()
+			.find(|q| q.name == name)
+			.unwrap_or_else(|| panic!("no question `{}`", name))
+	}
+
+	/// The theory of the question `name`.
+	pub fn theory(&self, name: &str) -> &TheoryNode {
+		&self.question(name).theory
+	}
+}
+
+fn lock() -> MutexGuard<'static, ()> {
+	// a test that failed while parsing doesn't break the others
+	let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+	set_cli_args_empty();
+	lock
+}
+
+fn try_parse(src: &str) -> (Option<Context>, MutexGuard<'static, ()>) {
+	let lock = lock();
+	let filename = String::from("test.mqs");
+	let source = SOURCES!().new_raw_source(filename.clone(), src.to_string());
+	let tokens = Lexer::new(filename.clone(), source).lex();
+	(Parser::new().parse(filename, tokens).ok(), lock)
+}
+
+/// Parses a script, which has to be free of errors.
+pub fn parse(src: &str) -> Script {
+	match try_parse(src) {
+		(Some(context), _lock) => Script { context, _lock },
+		(None, _) => panic!("could not parse:\n{}", src),
+	}
+}
+
+/// Whether a script has errors.
+pub fn fails_to_parse(src: &str) -> bool {
+	try_parse(src).0.is_none()
+}
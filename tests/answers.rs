@@ -0,0 +1,92 @@
+//! Solves the questions in `test/answers` and compares their answers with
+//! the ones expected in the comment after each of them, as in
+//! `?two := 1 + 1 == 2 -- expect: true`. A file may pass arguments to the
+//! interpreter on a line of its own, as in `-- args: --strategy egraph`.
+
+use std::{fs, path::{Path, PathBuf}, process::Command};
+
+const ANSWERS_DIR: &str = "test/answers";
+
+fn run(path: &Path, args: &[&str]) -> String {
+	let output = Command::new(env!("CARGO_BIN_EXE_mqs"))
+		.arg(path)
+		.args(args)
+		.output()
+		.expect("could not run the interpreter");
+	String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// The answers the questions of a script are expected to have, by name.
+fn expected(src: &str) -> Vec<(String, String)> {
+	src.lines()
+		.filter_map(|line| {
+			let (question, expectation) = line.split_once("-- expect:")?;
+			let name = question.trim_start().strip_prefix('?')?
+				.split(|c: char| !c.is_alphanumeric() && c != '_' && c != '\'')
+				.next()?;
+			Some((name.to_string(), expectation.trim().to_string()))
+		})
+		.collect()
+}
+
+/// The answers the interpreter gave, by name.
+fn answers(output: &str) -> Vec<(String, String)> {
+	let mut answers = Vec::new();
+	let mut name = None;
+	for line in output.lines() {
+		if let Some(n) = line.strip_prefix("question to solve: ?") {
+			name = Some(n.to_string());
+		} else if let (Some(answer), Some(n)) = (line.trim().strip_prefix("answer: "), &name) {
+			// as in `correct (true)` or `gave up (unknown)`
+			let truth = answer.rsplit_once('(').map_or(answer, |(_, t)| t.trim_end_matches(')'));
+			answers.push((n.clone(), truth.to_string()));
+		}
+	}
+	answers
+}
+
+#[test]
+fn answers_are_as_expected() {
+	let mut paths: Vec<PathBuf> = fs::read_dir(ANSWERS_DIR).expect("no answers directory")
+		.map(|e| e.unwrap().path())
+		.filter(|p| p.extension().is_some_and(|e| e == "mqs"))
+		.collect();
+	paths.sort();
+
+	let mut failures = Vec::new();
+	for path in paths {
+		let src = fs::read_to_string(&path).unwrap();
+		let args: Vec<&str> = src.lines()
+			.filter_map(|l| l.strip_prefix("-- args:"))
+			.flat_map(|a| a.split_whitespace())
+			.collect();
+		let answers = answers(&run(&path, &args));
+
+		for (name, expected) in expected(&src) {
+			match answers.iter().find(|(n, _)| *n == name) {
+				Some((_, answer)) if *answer == expected => {},
+				Some((_, answer)) => failures.push(format!("{}: ?{} is {}, expected {}", path.display(), name, answer, expected)),
+				None => failures.push(format!("{}: ?{} wasn't answered", path.display(), name)),
+			}
+		}
+	}
+
+	assert!(failures.is_empty(), "\n{}\n", failures.join("\n"));
+}
+
+#[test]
+fn markdown_matches_test_md() {
+	let output = run(Path::new("test/test.mqs"), &["--markdown"]);
+	assert_eq!(output, fs::read_to_string("test/test.md").unwrap());
+}
+
+#[test]
+fn review_shows_the_same_answers() {
+	// a copy of its own, since the object file is named after the script
+	let path = std::env::temp_dir().join("mqs-review-counterexamples.mqs");
+	fs::copy(Path::new(ANSWERS_DIR).join("counterexamples.mqs"), &path).unwrap();
+
+	let output = run(&path, &[]);
+	assert!(output.contains("counterexample: x = 0"));
+	assert_eq!(run(&path, &["--review"]), output);
+}